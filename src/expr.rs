@@ -0,0 +1,179 @@
+//! Boolean expressions of ``cfg(...)`` targets and ``when`` of deps.
+
+use anyhow::{bail, ensure, Context, Result};
+use std::collections::HashSet;
+
+/// ``any(...)``, ``all(...)``, ``not(...)``, ``name`` or ``key = "value"``.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Expr {
+    Any(Vec<Expr>),
+    All(Vec<Expr>),
+    Not(Box<Expr>),
+    Name(String),
+    KeyValue(String, String),
+}
+
+impl Expr {
+    pub fn parse(s: &str) -> Result<Self> {
+        let mut parser = Parser { s, i: 0 };
+        let expr = parser
+            .expr()
+            .and_then(|expr| {
+                parser.skip_whitespaces();
+                ensure!(
+                    parser.i == s.len(),
+                    "Unexpected {:?} at {}.",
+                    &s[parser.i..],
+                    parser.i
+                );
+                Ok(expr)
+            })
+            .with_context(|| format!("Failed with {s:?} expression."))?;
+        Ok(expr)
+    }
+
+    /// Parse ``cfg(expr)``.
+    pub fn parse_cfg(s: &str) -> Result<Self> {
+        Self::parse(
+            s.trim()
+                .strip_prefix("cfg(")
+                .and_then(|x| x.strip_suffix(')'))
+                .with_context(|| format!("{s:?} target isn't cfg(...)."))?,
+        )
+    }
+
+    /// ``is_true`` is called with (name, None)s and (key, Some(value))s.
+    pub fn eval(&self, is_true: &impl Fn(&str, Option<&str>) -> bool) -> bool {
+        match self {
+            Expr::Any(exprs) => exprs.iter().any(|x| x.eval(is_true)),
+            Expr::All(exprs) => exprs.iter().all(|x| x.eval(is_true)),
+            Expr::Not(expr) => !expr.eval(is_true),
+            Expr::Name(name) => is_true(name, None),
+            Expr::KeyValue(key, value) => is_true(key, Some(value)),
+        }
+    }
+
+    /// Evaluate against ``cfgs`` of (name, None)s and (key, Some(value))s, which are true.
+    #[must_use]
+    pub fn eval_cfgs(&self, cfgs: &HashSet<(String, Option<String>)>) -> bool {
+        self.eval(&|key, value| cfgs.contains(&(key.to_string(), value.map(str::to_string))))
+    }
+
+    /// Return names of the expression. Fail on ``key = "value"``s.
+    pub fn names(&self) -> Result<Vec<&str>> {
+        Ok(match self {
+            Expr::Any(exprs) | Expr::All(exprs) => {
+                let mut names = vec![];
+                for expr in exprs {
+                    names.extend(expr.names()?);
+                }
+                names
+            }
+            Expr::Not(expr) => expr.names()?,
+            Expr::Name(name) => vec![name],
+            Expr::KeyValue(key, value) => bail!("Unexpected {key} = {value:?}. Use names only."),
+        })
+    }
+}
+
+/// Return cfgs of the host with ``cfgs``, which are ``name``s or ``key=value``s.
+#[must_use]
+pub fn host_cfgs(cfgs: &HashSet<String>) -> HashSet<(String, Option<String>)> {
+    let mut host_cfgs: HashSet<(String, Option<String>)> = [
+        ("target_os", std::env::consts::OS),
+        ("target_arch", std::env::consts::ARCH),
+        ("target_family", std::env::consts::FAMILY),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), Some(value.to_string())))
+    .collect();
+    host_cfgs.insert((std::env::consts::FAMILY.to_string(), None));
+    for cfg in cfgs {
+        host_cfgs.insert(match cfg.split_once('=') {
+            Some((key, value)) => (
+                key.trim().to_string(),
+                Some(value.trim().trim_matches('"').to_string()),
+            ),
+            None => (cfg.trim().to_string(), None),
+        });
+    }
+    host_cfgs
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    i: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespaces(&mut self) {
+        self.i = self.s.len() - self.s[self.i..].trim_start().len();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespaces();
+        if self.s[self.i..].starts_with(c) {
+            self.i += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        ensure!(self.eat(c), "Expected {c:?} at {}.", self.i);
+        Ok(())
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        self.skip_whitespaces();
+        let len = self.s[self.i..]
+            .find(|x: char| !(x.is_alphanumeric() || x == '_' || x == '-'))
+            .unwrap_or(self.s.len() - self.i);
+        ensure!(len != 0, "Expected a name at {}.", self.i);
+        self.i += len;
+        Ok(self.s[self.i - len..self.i].to_string())
+    }
+
+    fn string(&mut self) -> Result<String> {
+        self.expect('"')?;
+        let len = self.s[self.i..]
+            .find('"')
+            .with_context(|| format!("Unclosed string at {}.", self.i))?;
+        self.i += len + 1;
+        Ok(self.s[self.i - len - 1..self.i - 1].to_string())
+    }
+
+    /// Parse comma separated exprs till ``)``.
+    fn exprs(&mut self) -> Result<Vec<Expr>> {
+        let mut exprs = vec![];
+        while !self.eat(')') {
+            exprs.push(self.expr()?);
+            if !self.eat(',') {
+                self.expect(')')?;
+                break;
+            }
+        }
+        Ok(exprs)
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let name = self.ident()?;
+        if self.eat('(') {
+            return Ok(match name.as_str() {
+                "any" => Expr::Any(self.exprs()?),
+                "all" => Expr::All(self.exprs()?),
+                "not" => {
+                    let expr = self.expr()?;
+                    self.expect(')')?;
+                    Expr::Not(Box::new(expr))
+                }
+                _ => bail!("Unknown {name:?} function. Use any, all or not."),
+            });
+        }
+        if self.eat('=') {
+            return Ok(Expr::KeyValue(name, self.string()?));
+        }
+        Ok(Expr::Name(name))
+    }
+}
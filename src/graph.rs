@@ -0,0 +1,505 @@
+//! Views of the deps graph, which is stored in the ``BUILD_FILE_NAME`` file.
+
+use crate::{BuildFile, BuildUnit, DepReq, LockType};
+use anyhow::{Context, Result};
+use petgraph::{
+    prelude::{EdgeIndex, EdgeRef, NodeIndex},
+    Direction, Graph,
+};
+use semver::Version;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// A deps graph. Edges go from units to their deps.
+pub struct DepGraph {
+    pub graph: Graph<BuildUnit, DepEdge>,
+    pub root: NodeIndex,
+    /// Numbers of sccs of size > 1, which nodes are contained in.
+    /// Sccs are numbered in order of their least dirs.
+    pub cycles: HashMap<NodeIndex, usize>,
+}
+
+/// An edge of a ``DepGraph``.
+pub struct DepEdge {
+    /// A dep name from ``name_map``.
+    pub name: String,
+    pub dev: bool,
+    pub req: Option<DepReq>,
+}
+
+/// A kind of edges to show.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DepKind {
+    Normal,
+    Dev,
+}
+
+impl DepGraph {
+    pub fn new(build_file: BuildFile) -> Result<Self> {
+        let mut graph = Graph::new();
+        let mut dir_is = HashMap::new();
+        let mut cycle_sccs = vec![];
+        for scc in build_file.sccs {
+            let is_cycle = scc.len() > 1;
+            let mut scc_is = vec![];
+            for unit in scc {
+                let dir = unit.dir.clone();
+                let i = graph.add_node(unit);
+                dir_is.entry(dir).or_insert(i);
+                scc_is.push(i);
+            }
+            if is_cycle {
+                cycle_sccs.push(scc_is);
+            }
+        }
+        cycle_sccs.sort_unstable_by_key(|x| x.iter().map(|y| graph[*y].dir.clone()).min());
+        let cycles = cycle_sccs
+            .into_iter()
+            .enumerate()
+            .flat_map(|(n, x)| x.into_iter().map(move |y| (y, n + 1)))
+            .collect();
+        let mut edges = vec![];
+        for i in graph.node_indices() {
+            let unit = &graph[i];
+            for (name, dir) in &unit.name_map {
+                edges.push((
+                    i,
+                    *dir_is
+                        .get(dir)
+                        .with_context(|| format!("There is no {dir:?} unit."))?,
+                    DepEdge {
+                        name: name.clone(),
+                        dev: unit.dev_deps.contains(name),
+                        req: unit.dep_reqs.get(name).cloned(),
+                    },
+                ));
+            }
+        }
+        for (from, to, edge) in edges {
+            graph.add_edge(from, to, edge);
+        }
+        let root = graph
+            .node_indices()
+            .find(|x| graph[*x].root)
+            .context("There is no root unit.")?;
+        Ok(DepGraph {
+            graph,
+            root,
+            cycles,
+        })
+    }
+
+    /// Return units, which have ``query`` as a package name, a dir or a repo.
+    #[must_use]
+    pub fn find(&self, query: &str) -> Vec<NodeIndex> {
+        self.graph
+            .node_indices()
+            .filter(|x| {
+                let unit = &self.graph[*x];
+                unit.name == query
+                    || unit.dir == query
+                    || unit.lock.as_ref().is_some_and(|x| x.repo == query)
+            })
+            .collect()
+    }
+
+    /// Return groups of units with the same repo, but different locks.
+    #[must_use]
+    pub fn duplicates(&self) -> Vec<Vec<NodeIndex>> {
+        let mut repos: BTreeMap<&str, Vec<NodeIndex>> = BTreeMap::new();
+        for i in self.graph.node_indices() {
+            if let Some(lock) = &self.graph[i].lock {
+                repos.entry(&lock.repo).or_default().push(i);
+            }
+        }
+        repos
+            .into_values()
+            .filter(|x| {
+                // Subdir units of one clone share one lock, so they aren't duplicates.
+                x.iter()
+                    .map(|y| self.graph[*y].lock.as_ref())
+                    .collect::<HashSet<_>>()
+                    .len()
+                    > 1
+            })
+            .map(|mut x| {
+                x.sort_unstable_by(|y, z| self.graph[*y].dir.cmp(&self.graph[*z].dir));
+                x
+            })
+            .collect()
+    }
+
+    /// Return a unit description with its lock and options.
+    #[must_use]
+    pub fn label(&self, i: NodeIndex) -> String {
+        let unit = &self.graph[i];
+        let mut label = vec![unit.name.clone()];
+        if let Some(version) = &unit.version {
+            label.push(format!("v{version}"));
+        }
+        match unit.lock.as_ref().map(|x| &x.lock_type) {
+            Some(LockType::Branch(branch)) => label.push(format!("branch {branch}")),
+            Some(LockType::Commit(commit)) => label.push(format!("commit {commit}")),
+            None => (),
+        }
+        if !unit.options.is_empty() {
+            label.push(format!(
+                "[{}]",
+                unit.options.iter().cloned().collect::<Vec<_>>().join(", ")
+            ));
+        }
+        if let Some(ncycle) = self.cycles.get(&i) {
+            label.push(format!("(cycle {ncycle})"));
+        }
+        label.join(" ")
+    }
+
+    /// Return nodes, which are sorted by dirs, and the root is the first.
+    #[must_use]
+    pub fn sorted_nodes(&self) -> Vec<NodeIndex> {
+        let mut nodes: Vec<_> = self.graph.node_indices().collect();
+        nodes.sort_unstable_by_key(|x| (!self.graph[*x].root, &self.graph[*x].dir));
+        nodes
+    }
+
+    fn is_shown(&self, from: NodeIndex, edge: &DepEdge, edges: Option<DepKind>) -> bool {
+        from != self.root
+            || match edges {
+                Some(DepKind::Normal) => !edge.dev,
+                Some(DepKind::Dev) => edge.dev,
+                None => true,
+            }
+    }
+}
+
+#[derive(Default)]
+pub struct TreeOptions {
+    /// A max depth of shown deps. The root has zero depth.
+    pub depth: Option<usize>,
+    /// Show units, which depend on the dep, instead of deps.
+    pub invert: Option<String>,
+    /// Show only units with the same repo, but different locks.
+    pub duplicates: bool,
+    /// Show only deps, which are reached through root deps of the kind.
+    pub edges: Option<DepKind>,
+}
+
+/// Write the deps tree.
+/// Already shown units are marked with ``(*)`` and their deps aren't shown again.
+pub fn tree(
+    dep_graph: &DepGraph,
+    options: &TreeOptions,
+    buffer: &mut impl std::io::Write,
+) -> Result<()> {
+    let (starts, direction) = if options.duplicates {
+        (dep_graph.duplicates().concat(), Direction::Incoming)
+    } else if let Some(query) = &options.invert {
+        let starts = dep_graph.find(query);
+        anyhow::ensure!(!starts.is_empty(), "There is no {query:?} dep.");
+        (starts, Direction::Incoming)
+    } else {
+        (vec![dep_graph.root], Direction::Outgoing)
+    };
+    for (n, start) in starts.into_iter().enumerate() {
+        if n != 0 {
+            writeln!(buffer)?;
+        }
+        writeln!(buffer, "{}", dep_graph.label(start))?;
+        tree_h(
+            dep_graph,
+            options,
+            direction,
+            start,
+            "",
+            1,
+            &mut HashSet::from([start]),
+            buffer,
+        )?;
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn tree_h(
+    dep_graph: &DepGraph,
+    options: &TreeOptions,
+    direction: Direction,
+    i: NodeIndex,
+    prefix: &str,
+    depth: usize,
+    shown: &mut HashSet<NodeIndex>,
+    buffer: &mut impl std::io::Write,
+) -> Result<()> {
+    if options.depth.is_some_and(|x| depth > x) {
+        return Ok(());
+    }
+    let mut edges: Vec<_> = dep_graph
+        .graph
+        .edges_directed(i, direction)
+        .filter(|x| dep_graph.is_shown(x.source(), x.weight(), options.edges))
+        .collect();
+    edges.sort_unstable_by(|x, y| {
+        (&x.weight().name, &dep_graph.graph[x.source()].dir)
+            .cmp(&(&y.weight().name, &dep_graph.graph[y.source()].dir))
+    });
+    for (n, edge) in edges.iter().enumerate() {
+        let next = match direction {
+            Direction::Outgoing => edge.target(),
+            Direction::Incoming => edge.source(),
+        };
+        let is_last = n + 1 == edges.len();
+        let mut line = if direction == Direction::Outgoing {
+            edge_label(dep_graph, edge.weight(), next)
+        } else {
+            dep_graph.label(next)
+        };
+        if edge.weight().dev {
+            line += " (dev)";
+        }
+        let is_new = shown.insert(next);
+        if !is_new {
+            line += " (*)";
+        }
+        writeln!(
+            buffer,
+            "{prefix}{}{line}",
+            if is_last { "└── " } else { "├── " }
+        )?;
+        if is_new {
+            tree_h(
+                dep_graph,
+                options,
+                direction,
+                next,
+                &(prefix.to_string() + if is_last { "    " } else { "│   " }),
+                depth + 1,
+                shown,
+                buffer,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Return a label of the edge target with the dep name, if it differs from the package name.
+fn edge_label(dep_graph: &DepGraph, edge: &DepEdge, to: NodeIndex) -> String {
+    let label = dep_graph.label(to);
+    if edge.name == dep_graph.graph[to].name {
+        label
+    } else {
+        format!("{} = {label}", edge.name)
+    }
+}
+
+/// Write all paths from the root to units, which have ``query`` as a package name, a dir or a repo.
+/// Each hop is shown with the option, which enabled it, and the version requirement,
+/// which selected its commit.
+pub fn why(dep_graph: &DepGraph, query: &str, buffer: &mut impl std::io::Write) -> Result<()> {
+    let targets: HashSet<NodeIndex> = dep_graph.find(query).into_iter().collect();
+    anyhow::ensure!(!targets.is_empty(), "There is no {query:?} dep.");
+    let mut paths = vec![];
+    why_h(
+        dep_graph,
+        &targets,
+        dep_graph.root,
+        &mut vec![],
+        &mut HashSet::from([dep_graph.root]),
+        &mut paths,
+    );
+    for (n, path) in paths.into_iter().enumerate() {
+        if n != 0 {
+            writeln!(buffer)?;
+        }
+        writeln!(buffer, "{}", dep_graph.label(dep_graph.root))?;
+        for (depth, edge) in path.into_iter().enumerate() {
+            let weight = dep_graph.graph.edge_weight(edge).unwrap();
+            let mut line = vec![edge_label(
+                dep_graph,
+                weight,
+                dep_graph.graph.edge_endpoints(edge).unwrap().1,
+            )];
+            if weight.dev {
+                line.push("(dev)".to_string());
+            }
+            if let Some(req) = &weight.req {
+                if let Some(option_name) = &req.option_name {
+                    line.push(format!("(option_name = {option_name:?})"));
+                }
+                if let Some(when) = &req.when {
+                    line.push(format!("(when = {when:?})"));
+                }
+                if let Some(version) = &req.version {
+                    line.push(format!("(version = \"{version}\")"));
+                }
+            }
+            writeln!(buffer, "{}└── {}", "    ".repeat(depth), line.join(" "))?;
+        }
+    }
+    Ok(())
+}
+
+fn why_h(
+    dep_graph: &DepGraph,
+    targets: &HashSet<NodeIndex>,
+    i: NodeIndex,
+    path: &mut Vec<EdgeIndex>,
+    on_path: &mut HashSet<NodeIndex>,
+    paths: &mut Vec<Vec<EdgeIndex>>,
+) {
+    if targets.contains(&i) {
+        paths.push(path.clone());
+    }
+    let mut edges: Vec<_> = dep_graph.graph.edges(i).collect();
+    edges.sort_unstable_by_key(|x| &x.weight().name);
+    for edge in edges {
+        if on_path.insert(edge.target()) {
+            path.push(edge.id());
+            why_h(dep_graph, targets, edge.target(), path, on_path, paths);
+            path.pop();
+            on_path.remove(&edge.target());
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonGraph<'a> {
+    nodes: Vec<JsonNode<'a>>,
+    edges: Vec<JsonEdge<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonNode<'a> {
+    id: usize,
+    name: &'a str,
+    root: bool,
+    repo: Option<&'a str>,
+    #[serde(flatten)]
+    lock_type: Option<&'a LockType>,
+    version: Option<&'a Version>,
+    options: &'a BTreeSet<String>,
+    /// A number of the scc of size > 1, which the node is contained in.
+    cycle: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct JsonEdge<'a> {
+    from: usize,
+    to: usize,
+    name: &'a str,
+    dev: bool,
+}
+
+/// Return graph edges as (from, to, edge) with ids of ``sorted_nodes``, which are sorted.
+fn sorted_edges(dep_graph: &DepGraph) -> Vec<(usize, usize, &DepEdge)> {
+    let ids: HashMap<NodeIndex, usize> = dep_graph
+        .sorted_nodes()
+        .into_iter()
+        .enumerate()
+        .map(|(n, x)| (x, n))
+        .collect();
+    let mut edges: Vec<_> = dep_graph
+        .graph
+        .edge_references()
+        .map(|x| (ids[&x.source()], ids[&x.target()], x.weight()))
+        .collect();
+    edges.sort_unstable_by(|x, y| (x.0, &x.2.name).cmp(&(y.0, &y.2.name)));
+    edges
+}
+
+/// Write the deps graph in the JSON format.
+pub fn json(dep_graph: &DepGraph, buffer: &mut impl std::io::Write) -> Result<()> {
+    let nodes = dep_graph
+        .sorted_nodes()
+        .into_iter()
+        .enumerate()
+        .map(|(n, x)| {
+            let unit = &dep_graph.graph[x];
+            JsonNode {
+                id: n,
+                name: &unit.name,
+                root: unit.root,
+                repo: unit.lock.as_ref().map(|x| x.repo.as_str()),
+                lock_type: unit.lock.as_ref().map(|x| &x.lock_type),
+                version: unit.version.as_ref(),
+                options: &unit.options,
+                cycle: dep_graph.cycles.get(&x).copied(),
+            }
+        })
+        .collect();
+    let edges = sorted_edges(dep_graph)
+        .into_iter()
+        .map(|(from, to, edge)| JsonEdge {
+            from,
+            to,
+            name: &edge.name,
+            dev: edge.dev,
+        })
+        .collect();
+    serde_json::to_writer_pretty(&mut *buffer, &JsonGraph { nodes, edges })?;
+    writeln!(buffer)?;
+    Ok(())
+}
+
+/// Write the deps graph in the DOT format.
+/// Sccs of size > 1 are clusters. Dev edges are dashed.
+pub fn dot(dep_graph: &DepGraph, buffer: &mut impl std::io::Write) -> Result<()> {
+    writeln!(buffer, "digraph deps {{")?;
+    let mut clusters: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (n, i) in dep_graph.sorted_nodes().into_iter().enumerate() {
+        let unit = &dep_graph.graph[i];
+        let mut label = vec![unit.name.clone()];
+        if let Some(lock) = &unit.lock {
+            label.push(lock.repo.clone());
+            label.push(match &lock.lock_type {
+                LockType::Branch(branch) => format!("branch {branch}"),
+                LockType::Commit(commit) => format!("commit {commit}"),
+            });
+        }
+        if let Some(version) = &unit.version {
+            label.push(format!("v{version}"));
+        }
+        writeln!(
+            buffer,
+            "    n{n} [label={}];",
+            dot_string(&label.join("\n"))
+        )?;
+        if let Some(ncycle) = dep_graph.cycles.get(&i) {
+            clusters.entry(*ncycle).or_default().push(n);
+        }
+    }
+    for (ncycle, ns) in clusters {
+        writeln!(
+            buffer,
+            "    subgraph cluster_{ncycle} {{ label=\"cycle {ncycle}\"; {} }}",
+            ns.iter()
+                .map(|x| format!("n{x};"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        )?;
+    }
+    for (from, to, edge) in sorted_edges(dep_graph) {
+        writeln!(
+            buffer,
+            "    n{from} -> n{to} [label={}{}];",
+            dot_string(&edge.name),
+            if edge.dev { ", style=dashed" } else { "" }
+        )?;
+    }
+    writeln!(buffer, "}}")?;
+    Ok(())
+}
+
+/// Return ``string`` as a quoted DOT string.
+fn dot_string(string: &str) -> String {
+    let mut quoted = String::from('"');
+    for char in string.chars() {
+        match char {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            _ => quoted.push(char),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -1,29 +1,226 @@
 use anyhow::{ensure, Context, Result};
-use bimap::BiMap;
-use petgraph::{prelude::NodeIndex, Graph};
+use petgraph::{prelude::NodeIndex, visit::Dfs, Graph};
 use semver::Version;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{BTreeMap, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     ffi::OsString,
     fs,
     path::{Path, PathBuf},
     process::Command,
 };
 
+pub mod expr;
+pub mod graph;
+pub mod manifest;
+pub mod outdated;
+pub mod project;
+
 pub const CFG_FILE_NAME: &str = "crack.toml";
 const LOCK_FILE_NAME: &str = "crack.lock";
 pub const BUILD_FILE_NAME: &str = "crack.build";
+/// A version of the ``BUILD_FILE_NAME`` file format.
+pub const BUILD_FILE_VERSION: u32 = 9;
+/// An env var with a deps dir, which overrides the cfg one.
+pub const DEPS_DIR_VAR: &str = "CRACK_DEPS_DIR";
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct Cfg {
     name: String,
+    /// A package version.
+    #[serde(default)]
+    version: Option<Version>,
+    #[serde(default)]
+    authors: Vec<String>,
+    /// An SPDX license expression.
+    #[serde(default)]
+    license: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    homepage: Option<String>,
+    #[serde(default)]
+    keywords: Vec<String>,
     #[serde(default = "default_interpreter")]
     pub interpreter: PathBuf,
     #[serde(default)]
     dev_deps: Vec<Dep>,
     #[serde(default)]
     deps: Vec<Dep>,
+    /// Options to options, which they enable.
+    /// ``default`` option is always enabled.
+    #[serde(default)]
+    options: BTreeMap<String, Vec<String>>,
+    /// Groups of deps, which are installed only on request.
+    #[serde(default)]
+    groups: BTreeMap<String, Group>,
+    /// ``cfg(...)`` targets to their deps.
+    /// They are moved to ``deps`` and ``dev_deps`` with ``target`` on reading.
+    #[serde(default)]
+    target: BTreeMap<String, Target>,
+    #[serde(default)]
+    pub workspace: Workspace,
+    /// A deps dir relative to the cfg dir. It's ``deps`` by default.
+    #[serde(default)]
+    pub deps_dir: Option<PathBuf>,
+    /// A requirement of the crack version, which installs the package.
+    #[serde(default)]
+    crack_version: Option<semver::VersionReq>,
+    /// A requirement of the ``interpreter --version`` version.
+    #[serde(default)]
+    interpreter_version: Option<semver::VersionReq>,
+}
+
+/// Version requirements, which the ``package`` cfg declared.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Requirements {
+    #[serde(rename = "name")]
+    package: String,
+    crack_version: Option<semver::VersionReq>,
+    interpreter_version: Option<semver::VersionReq>,
+}
+
+/// Fail with all ``requirements``, which aren't satisfied by the crack version
+/// or the ``interpreter`` version. The interpreter is run only if there are its requirements.
+pub fn check_requirements(requirements: &[Requirements], interpreter: &Path) -> Result<()> {
+    let crack_version = Version::parse(env!("CARGO_PKG_VERSION"))?;
+    let mut unsatisfied = vec![];
+    let mut interpreter_version = None;
+    for x in requirements {
+        if let Some(req) = &x.crack_version {
+            if !req.matches(&crack_version) {
+                unsatisfied.push(format!(
+                    "{:?} package requires {req} crack version, but it's {crack_version}.",
+                    x.package
+                ));
+            }
+        }
+        if let Some(req) = &x.interpreter_version {
+            if interpreter_version.is_none() {
+                interpreter_version = Some(self::interpreter_version(interpreter)?);
+            }
+            let version = interpreter_version.as_ref().unwrap();
+            if !req.matches(version) {
+                unsatisfied.push(format!(
+                    "{:?} package requires {req} interpreter version, but {interpreter:#?} is {version}.",
+                    x.package
+                ));
+            }
+        }
+    }
+    ensure!(unsatisfied.is_empty(), "{}", unsatisfied.join("\n"));
+    Ok(())
+}
+
+/// Return the first version in ``interpreter --version`` output.
+fn interpreter_version(interpreter: &Path) -> Result<Version> {
+    let output = Command::new(interpreter)
+        .arg("--version")
+        .output()
+        .with_context(|| format!("Failed with {interpreter:#?} interpreter."))?;
+    with_stderr(&output).with_context(|| format!("Failed with {interpreter:#?} interpreter."))?;
+    std::str::from_utf8(&output.stdout)?
+        .split_whitespace()
+        .find_map(|x| Version::parse(x.trim_start_matches('v')).ok())
+        .with_context(|| format!("There is no version in {interpreter:#?} --version output."))
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Group {
+    #[serde(default)]
+    deps: Vec<Dep>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct Workspace {
+    /// Dirs of packages, which are resolved together with the root.
+    /// They are relative to the root dir.
+    #[serde(default)]
+    pub members: Vec<PathBuf>,
+}
+
+impl Workspace {
+    /// Return canonical dirs of members.
+    pub fn member_dirs(&self, cfg_dir: &Path) -> Result<BTreeSet<PathBuf>> {
+        self.members
+            .iter()
+            .map(|x| {
+                fs::canonicalize(cfg_dir.join(x))
+                    .with_context(|| format!("Failed with {x:#?} workspace member."))
+            })
+            .collect()
+    }
+}
+
+/// Return the root dir of the workspace, which ``project_dir`` is a member of,
+/// or ``project_dir``.
+pub fn workspace_root(project_dir: &Path) -> Result<PathBuf> {
+    let canonical_dir = fs::canonicalize(project_dir)?;
+    for dir in project_dir.ancestors().skip(1) {
+        if dir.join(CFG_FILE_NAME).exists()
+            && Cfg::lenient(dir)?
+                .workspace
+                .member_dirs(dir)?
+                .contains(&canonical_dir)
+        {
+            return Ok(dir.to_path_buf());
+        }
+    }
+    Ok(project_dir.to_path_buf())
+}
+
+/// Return the deps dir of the project in ``cfg_dir``: ``deps_dir``, the ``DEPS_DIR_VAR`` env var,
+/// the cfg ``deps_dir`` or ``deps`` in that order. Relative dirs are relative to ``cfg_dir``.
+/// Fail, if the deps dir is ``cfg_dir`` or its ancestor.
+pub fn deps_dir(cfg_dir: &Path, deps_dir: Option<PathBuf>) -> Result<PathBuf> {
+    let deps_dir = match deps_dir.or_else(|| std::env::var_os(DEPS_DIR_VAR).map(PathBuf::from)) {
+        Some(deps_dir) => deps_dir,
+        None => Cfg::lenient(cfg_dir)?
+            .deps_dir
+            .unwrap_or_else(|| PathBuf::from("deps")),
+    };
+    let deps_dir = cfg_dir.join(deps_dir);
+    ensure!(
+        !normalized(cfg_dir)?.starts_with(normalized(&deps_dir)?),
+        "{deps_dir:#?} deps dir contains {cfg_dir:#?} project dir."
+    );
+    Ok(deps_dir)
+}
+
+/// Return absolute ``path`` without ``.`` and ``..`` components.
+fn normalized(path: &Path) -> Result<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in std::path::absolute(path)?.components() {
+        match component {
+            std::path::Component::CurDir => (),
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            _ => normalized.push(component),
+        }
+    }
+    Ok(normalized)
+}
+
+/// Return the dir of the workspace member named ``name``.
+pub fn member_dir(cfg_dir: &Path, name: &str) -> Result<PathBuf> {
+    for dir in Cfg::lenient(cfg_dir)?.workspace.member_dirs(cfg_dir)? {
+        if Cfg::lenient(&dir)?.name == name {
+            return Ok(dir);
+        }
+    }
+    anyhow::bail!(
+        "There is no {name:?} workspace member in {:#?} cfg.",
+        cfg_dir.join(CFG_FILE_NAME)
+    )
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Target {
+    #[serde(default)]
+    deps: Vec<Dep>,
+    #[serde(default)]
+    dev_deps: Vec<Dep>,
 }
 
 fn default_interpreter() -> PathBuf {
@@ -31,24 +228,176 @@ fn default_interpreter() -> PathBuf {
 }
 
 impl Cfg {
+    #[must_use]
+    pub fn requirements(&self) -> Requirements {
+        Requirements {
+            package: self.name.clone(),
+            crack_version: self.crack_version.clone(),
+            interpreter_version: self.interpreter_version.clone(),
+        }
+    }
+
+    /// Read the cfg in ``dir``. Unknown keys are errors.
     pub fn new(dir: &Path) -> Result<Self> {
+        Ok(Self::with_warnings(dir, false)?.0)
+    }
+
+    /// Read the cfg in ``dir`` and return it with warnings about unknown keys.
+    /// Unknown keys are errors, unless ``lenient``. Invalid package metadata is an error.
+    pub fn with_warnings(dir: &Path, lenient: bool) -> Result<(Self, Vec<String>)> {
+        Self::read(dir, lenient, false)
+    }
+
+    /// Read the cfg of a dep, which isn't a workspace member, in ``dir`` and return it
+    /// with warnings about unknown keys and invalid package metadata.
+    pub fn of_dep(dir: &Path) -> Result<(Self, Vec<String>)> {
+        Self::read(dir, true, true)
+    }
+
+    /// Read the cfg in ``dir``. Unknown keys and invalid package metadata are errors,
+    /// unless ``lenient_keys`` and ``lenient_metadata``; then they are returned as warnings.
+    fn read(dir: &Path, lenient_keys: bool, lenient_metadata: bool) -> Result<(Self, Vec<String>)> {
         let cfg_path = dir.join(CFG_FILE_NAME);
-        toml::from_str(
-            &fs::read_to_string(&cfg_path)
-                .with_context(|| format!("Failed with {cfg_path:#?} cfg file."))?,
-        )
-        .with_context(|| format!("Failed with {cfg_path:#?} cfg file."))
+        let cfg_text = fs::read_to_string(&cfg_path)
+            .with_context(|| format!("Failed with {cfg_path:#?} cfg file."))?;
+        let unknown_keys = manifest::check_keys(&cfg_text)
+            .with_context(|| format!("Failed with {cfg_path:#?} cfg file."))?;
+        let mut warnings = vec![];
+        if lenient_keys {
+            for unknown_key in unknown_keys {
+                warnings.push(format!("Warning in {cfg_path:#?} cfg file. {unknown_key}"));
+            }
+        } else if !unknown_keys.is_empty() {
+            // Unknown keys can be keys of a newer crack, so its requirement goes first.
+            if let Ok(requirements) = toml::from_str::<Requirements>(&cfg_text) {
+                check_requirements(
+                    &[Requirements {
+                        interpreter_version: None,
+                        ..requirements
+                    }],
+                    Path::new(""),
+                )
+                .with_context(|| format!("Failed with {cfg_path:#?} cfg file."))?;
+            }
+            return Err(anyhow::anyhow!(unknown_keys.join("\n")))
+                .with_context(|| format!("Failed with {cfg_path:#?} cfg file."));
+        }
+        let mut cfg: Self = toml::from_str(&cfg_text)
+            .with_context(|| format!("Failed with {cfg_path:#?} cfg file."))?;
+        let metadata_problems = manifest::check_metadata(&cfg_text)
+            .with_context(|| format!("Failed with {cfg_path:#?} cfg file."))?;
+        if lenient_metadata {
+            for problem in metadata_problems {
+                warnings.push(format!("Warning in {cfg_path:#?} cfg file. {problem}"));
+            }
+        } else if !metadata_problems.is_empty() {
+            return Err(anyhow::anyhow!(metadata_problems.join("\n")))
+                .with_context(|| format!("Failed with {cfg_path:#?} cfg file."));
+        }
+        for (target, target_deps) in std::mem::take(&mut cfg.target) {
+            let with_target = |mut dep: Dep| {
+                dep.target = Some(target.clone());
+                dep
+            };
+            cfg.deps
+                .extend(target_deps.deps.into_iter().map(with_target));
+            cfg.dev_deps
+                .extend(target_deps.dev_deps.into_iter().map(with_target));
+        }
+        Ok((cfg, warnings))
+    }
+
+    /// Read the cfg in ``dir`` ignoring unknown keys and invalid package metadata.
+    fn lenient(dir: &Path) -> Result<Self> {
+        Ok(Self::read(dir, true, true)?.0)
+    }
+}
+
+/// Options of a unit.
+#[derive(Default)]
+struct ResolvedOptions {
+    options: BTreeSet<String>,
+    /// Dep names to options, which are enabled on the deps by ``dep/option`` entries.
+    dep_options: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// Return ``options`` with ``default`` and enabled by them options.
+/// Declared options are keys of ``declared`` and ``option_name``s of ``deps``.
+/// ``dep/option`` entries enable ``option`` on the dep named ``dep``.
+/// Fail on undeclared options, including options of ``when``s of ``deps``.
+fn resolve_options(
+    declared: &BTreeMap<String, Vec<String>>,
+    deps: &[Dep],
+    options: impl IntoIterator<Item = String>,
+) -> Result<ResolvedOptions> {
+    let is_declared = |option: &str| {
+        option.contains('/')
+            || declared.contains_key(option)
+            || deps
+                .iter()
+                .any(|x| x.option_name.as_deref() == Some(option))
+    };
+    for (option, enabled_options) in declared {
+        for enabled_option in enabled_options {
+            ensure!(
+                is_declared(enabled_option),
+                "{option:?} option enables undeclared {enabled_option:?} option."
+            );
+        }
     }
+    for dep in deps {
+        if let Some(when) = &dep.when {
+            for option in expr::Expr::parse(when)?.names()? {
+                ensure!(
+                    is_declared(option) && !option.contains('/'),
+                    "{when:?} when of {:?} dep contains undeclared {option:?} option.",
+                    dep.name.as_ref().or(dep.repo.as_ref())
+                );
+            }
+        }
+    }
+    let mut stack: Vec<String> = options.into_iter().collect();
+    if declared.contains_key("default") {
+        stack.push("default".to_string());
+    }
+    let mut resolved = ResolvedOptions::default();
+    while let Some(option) = stack.pop() {
+        ensure!(is_declared(&option), "{option:?} option isn't declared.");
+        if let Some((dep_name, dep_option)) = option.split_once('/') {
+            resolved
+                .dep_options
+                .entry(dep_name.to_string())
+                .or_default()
+                .insert(dep_option.to_string());
+            continue;
+        }
+        if let Some(enabled_options) = declared.get(&option) {
+            if !resolved.options.contains(&option) {
+                stack.extend(enabled_options.iter().cloned());
+            }
+        }
+        resolved.options.insert(option);
+    }
+    Ok(resolved)
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Dep {
     pub name: Option<String>,
-    pub repo: String,
+    pub repo: Option<String>,
+    /// A dir of a local package. It's relative to the dir of the dependent.
+    pub path: Option<PathBuf>,
+    /// A package dir in the repo.
+    pub subdir: Option<PathBuf>,
     #[serde(flatten)]
     pub dep_type: Option<DepType>,
     pub options: Option<Vec<String>>,
     pub option_name: Option<String>,
+    /// An expression of options with ``any(...)``, ``all(...)`` and ``not(...)``,
+    /// which enables the dep.
+    pub when: Option<String>,
+    /// A ``cfg(...)`` target, which the dep is enabled on.
+    pub target: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -62,9 +411,29 @@ pub enum DepType {
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct LockFile {
     pub root_deps: Vec<Dep>,
+    #[serde(default)]
+    pub root_dev_deps: Vec<Dep>,
     pub root_options: HashSet<String>,
     #[serde(default)]
     pub locks: Vec<LockUnit>,
+    /// Locks of deps, which are reached only through ``root_dev_deps``.
+    #[serde(default)]
+    pub dev_locks: Vec<LockUnit>,
+    /// Installed groups to their deps.
+    #[serde(default)]
+    pub root_groups: BTreeMap<String, Vec<Dep>>,
+    /// Groups to locks of deps, which are reached only through the groups.
+    #[serde(default)]
+    pub group_locks: BTreeMap<String, Vec<LockUnit>>,
+    /// User cfgs, which targets were evaluated with.
+    #[serde(default)]
+    pub root_cfgs: HashSet<String>,
+    /// Targets to locks of deps, which are reached only through the targets.
+    #[serde(default)]
+    pub target_locks: BTreeMap<String, Vec<LockUnit>>,
+    /// Dev deps weren't installed.
+    #[serde(default)]
+    pub root_no_dev: bool,
 }
 
 impl LockFile {
@@ -96,25 +465,129 @@ pub enum LockType {
     Commit(String),
 }
 
+/// A ``BUILD_FILE_NAME`` file.
+/// It's a stable contract with the Sanskrit interpreter, see README.
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BuildFile {
+    pub version: u32,
+    /// A deps dir path. It's relative to the project root, if the dir is inside it.
+    pub deps_dir: PathBuf,
+    /// Sccs of build units in reverse topological order.
+    pub sccs: Vec<Vec<BuildUnit>>,
+}
+
+impl BuildFile {
+    pub fn new(build_file_dir: &Path) -> Result<Self> {
+        Self::from_path(&build_file_dir.join(BUILD_FILE_NAME))
+    }
+
+    pub fn from_path(build_file_path: &Path) -> Result<Self> {
+        /// Other fields can differ between versions, so the version is read first.
+        #[derive(Deserialize)]
+        struct Versioned {
+            version: u32,
+        }
+
+        let build_file_text = fs::read_to_string(build_file_path)
+            .with_context(|| format!("Failed with {build_file_path:#?} build file."))?;
+        let version = serde_json::from_str::<Versioned>(&build_file_text)
+            .with_context(|| format!("Failed with {build_file_path:#?} build file."))?
+            .version;
+        ensure!(
+            version == BUILD_FILE_VERSION,
+            "{build_file_path:#?} build file has {version} version instead of {BUILD_FILE_VERSION}. Reinstall deps."
+        );
+        serde_json::from_str(&build_file_text)
+            .with_context(|| format!("Failed with {build_file_path:#?} build file."))
+    }
+
+    /// Return requirements of cfgs of all units. Unit paths are relative to ``project_dir``.
+    pub fn requirements(&self, project_dir: &Path) -> Result<Vec<Requirements>> {
+        self.sccs
+            .iter()
+            .flatten()
+            .map(|x| Ok(Cfg::lenient(&project_dir.join(&x.path))?.requirements()))
+            .collect()
+    }
+}
+
 /// A unit of a ``BUILD_FILE_NAME`` file.
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Serialize, Deserialize, Hash)]
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize, Clone)]
 pub struct BuildUnit {
+    /// A dir name in the deps dir or an absolute dir path for the root and path deps.
+    /// It's ``<dir name>/<subdir>`` for deps with ``subdir``.
     dir: OsString,
+    /// A package name from the unit cfg file.
+    name: String,
+    /// A unit dir path. It's relative to the project root, if the unit is inside it.
+    path: PathBuf,
+    root: bool,
+    /// The unit is a workspace member of the root.
+    member: bool,
+    /// The unit is reached only through dev deps of the root or members.
+    dev: bool,
+    /// Groups, which the unit is reached only through.
+    groups: BTreeSet<String>,
+    /// It's ``None`` for the root and path deps.
+    lock: Option<LockUnit>,
+    /// A version of the tag, which the commit was selected by.
+    version: Option<Version>,
+    options: BTreeSet<String>,
+    /// Dep names to their ``dir``s.
     name_map: BTreeMap<String, OsString>,
+    /// Dep names from ``name_map``, which are dev deps.
+    dev_deps: BTreeSet<String>,
+    /// Groups to dep names from ``name_map``, which are deps of the groups.
+    group_deps: BTreeMap<String, BTreeSet<String>>,
+    /// Dep names from ``name_map`` to requirements, which the deps are selected by.
+    /// Deps without requirements aren't contained.
+    dep_reqs: BTreeMap<String, DepReq>,
+}
+
+/// Requirements, which a dep is selected by.
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize, Hash, Clone)]
+pub struct DepReq {
+    /// An option, which enables the dep.
+    option_name: Option<String>,
+    /// An expression of options, which enables the dep.
+    when: Option<String>,
+    /// A requirement, which selects the dep commit.
+    version: Option<semver::VersionReq>,
 }
 
 /// ``install``, but deps are from the cfg file.
+/// Dev deps are skipped, if ``no_dev`` is set.
+/// Only deps of ``groups`` are installed from cfg groups.
+#[allow(clippy::too_many_arguments)]
 pub fn cfg_install(
     cfg_dir: &Path,
     deps_dir: &Path,
     options: &HashSet<String>,
+    cfgs: &HashSet<String>,
+    no_dev: bool,
+    groups: &BTreeSet<String>,
+    lenient: bool,
     installer: &impl Fn(&Path, &Path, &LockUnit) -> Result<()>,
-) -> Result<()> {
-    let mut cfg = Cfg::new(cfg_dir)?;
-    let mut deps = cfg.deps;
-    deps.append(&mut cfg.dev_deps);
-    install(cfg_dir, deps_dir, deps, options, &installer)?;
-    Ok(())
+) -> Result<Vec<String>> {
+    let mut cfg = Cfg::with_warnings(cfg_dir, lenient)?.0;
+    let dev_deps = (!no_dev).then_some(cfg.dev_deps);
+    let mut group_deps = BTreeMap::new();
+    for group in groups {
+        let deps = cfg
+            .groups
+            .remove(group)
+            .with_context(|| {
+                format!(
+                    "There is no {group:?} group in {:#?} cfg.",
+                    cfg_dir.join(CFG_FILE_NAME)
+                )
+            })?
+            .deps;
+        group_deps.insert(group.clone(), deps);
+    }
+    install(
+        cfg_dir, deps_dir, cfg.deps, dev_deps, group_deps, options, cfgs, lenient, &installer,
+    )
 }
 
 /// Clone branch deps in ``<repo_author>.<repo_name>.branch.<branch>`` dirs and
@@ -125,159 +598,754 @@ pub fn cfg_install(
 /// Write all deps, which must be contained in ``deps_dir``
 /// according to ``deps`` and its transitive deps, to the ``LOCK_FILE_NAME`` file and
 /// sccs of deps and root project in reverse topological order to the ``BUILD_FILE_NAME`` file.
+/// Every locked dep is a single unit. Its options are unioned across all its dependents.
+/// Deps with targets are enabled only if the targets match the host and ``cfgs``.
+/// Deps of all targets are cloned and locked, so the ``LOCK_FILE_NAME`` file is
+/// the same on all hosts.
+/// ``dev_deps`` are ``None``, if dev deps aren't installed.
+/// Unknown keys of cfgs of the root and members are errors, unless ``lenient``.
+/// Unknown keys of cfgs of other deps are returned as warnings.
+#[allow(clippy::too_many_arguments)]
 pub fn install(
     cfg_dir: &Path,
     deps_dir: &Path,
     deps: Vec<Dep>,
+    dev_deps: Option<Vec<Dep>>,
+    groups: BTreeMap<String, Vec<Dep>>,
     options: &HashSet<String>,
+    cfgs: &HashSet<String>,
+    lenient: bool,
     installer: &impl Fn(&Path, &Path, &LockUnit) -> Result<()>,
-) -> Result<()> {
+) -> Result<Vec<String>> {
     if !deps_dir.exists() {
         fs::create_dir_all(deps_dir)?;
     }
-    let mut graph = Graph::new();
-    let mut i_bimap = BiMap::new();
-    let mut installed_deps = vec![];
+    let (cfg, root_warnings) = Cfg::with_warnings(cfg_dir, lenient)?;
+    let no_dev = dev_deps.is_none();
+    let dev_deps = dev_deps.unwrap_or_default();
+    let group_deps: Vec<(String, Dep)> = groups
+        .iter()
+        .flat_map(|(group, deps)| deps.iter().map(|x| (group.clone(), x.clone())))
+        .collect();
+    let mut all_deps = vec![deps.as_slice(), dev_deps.as_slice()];
+    all_deps.extend(groups.values().map(Vec::as_slice));
+    let root_options = resolve_options(&cfg.options, &all_deps.concat(), options.iter().cloned())
+        .with_context(|| {
+        format!(
+            "Failed with {:#?} cfg options.",
+            cfg_dir.join(CFG_FILE_NAME)
+        )
+    })?;
+    let root_requirements = cfg.requirements();
+    let root = root_unit(cfg_dir, cfg.name, root_options.options)?;
+    let member_dirs = cfg.workspace.member_dirs(cfg_dir)?;
+    let mut resolver = Resolver::new(
+        cfg_dir,
+        deps_dir,
+        installer,
+        Some(expr::host_cfgs(cfgs)),
+        member_dirs.clone(),
+        HashMap::new(),
+        lenient,
+    );
+    let resolve = |resolver: &mut Resolver<_>| {
+        resolver.resolve(
+            &root,
+            &root_options.dep_options,
+            &deps,
+            &dev_deps,
+            &group_deps,
+            &root_requirements,
+        )
+    };
+    resolve(&mut resolver)?;
+    let Resolver {
+        mut graph,
+        requirements,
+        existing_versions,
+        has_targets,
+        mut warnings,
+        ..
+    } = resolver;
+    check_requirements(&requirements, &cfg.interpreter)?;
+    mark_unit_kinds(&mut graph);
     let mut lock_file = LockFile {
-        root_deps: deps.clone(),
+        root_deps: vec![],
+        root_dev_deps: vec![],
         root_options: options.clone(),
         locks: vec![],
+        dev_locks: vec![],
+        root_groups: groups,
+        group_locks: BTreeMap::new(),
+        root_cfgs: cfgs.clone(),
+        target_locks: BTreeMap::new(),
+        root_no_dev: no_dev,
     };
-    install_h(
-        OsString::from("root"),
-        None,
-        deps_dir,
-        deps,
+    if has_targets {
+        // Locks are the same on all hosts, so they are of the resolution with all targets.
+        let mut resolver = Resolver::new(
+            cfg_dir,
+            deps_dir,
+            installer,
+            None,
+            member_dirs,
+            existing_versions,
+            lenient,
+        );
+        resolve(&mut resolver)?;
+        fill_target_locks(&mut lock_file, resolver.graph, &resolver.target_edges);
+        for warning in resolver.warnings {
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        }
+    } else {
+        fill_locks(&mut lock_file, graph.node_weights());
+    }
+    lock_file.root_deps = deps;
+    lock_file.root_dev_deps = dev_deps;
+    write_files(cfg_dir, deps_dir, graph, &lock_file)?;
+    Ok([root_warnings, warnings].concat())
+}
+
+/// Return the root unit of the project in ``cfg_dir`` without deps.
+fn root_unit(cfg_dir: &Path, name: String, options: BTreeSet<String>) -> Result<BuildUnit> {
+    Ok(BuildUnit {
+        dir: std::path::absolute(cfg_dir)?.into_os_string(),
+        name,
+        path: PathBuf::from("."),
+        root: true,
+        member: false,
+        dev: false,
+        groups: BTreeSet::new(),
+        lock: None,
+        version: None,
         options,
-        &installer,
-        NodeIndex::from(0),
-        &mut i_bimap,
-        &mut graph,
-        &mut installed_deps,
-        &mut HashMap::new(),
-    )?;
-    let sccs: Vec<Vec<BuildUnit>> = petgraph::algo::kosaraju_scc(&graph)
-        .iter()
-        .map(|x| {
-            x.iter()
-                .map(|y| i_bimap.remove_by_right(y).unwrap().0)
-                .collect()
-        })
-        .collect();
-    lock_file.locks = installed_deps;
+        name_map: BTreeMap::new(),
+        dev_deps: BTreeSet::new(),
+        group_deps: BTreeMap::new(),
+        dep_reqs: BTreeMap::new(),
+    })
+}
+
+/// Write ``lock_file`` to the ``LOCK_FILE_NAME`` file and ``graph`` to
+/// ``BUILD_FILE_NAME`` files of the root and members.
+fn write_files(
+    cfg_dir: &Path,
+    deps_dir: &Path,
+    graph: Graph<BuildUnit, ()>,
+    lock_file: &LockFile,
+) -> Result<()> {
     fs::write(
         cfg_dir.join(LOCK_FILE_NAME),
-        toml::to_string(&lock_file).with_context(|| {
+        toml::to_string(lock_file).with_context(|| {
             format!("Failed with {:#?} lock file.", cfg_dir.join(LOCK_FILE_NAME))
         })?,
     )?;
+    for i in graph.node_indices() {
+        if graph[i].member {
+            write_member_build_file(cfg_dir, deps_dir, &graph, i)?;
+        }
+    }
+    write_build_file(cfg_dir, unit_path(cfg_dir, deps_dir), graph)
+}
+
+/// Write sccs of ``graph`` in reverse topological order and ``deps_dir`` path
+/// to the ``BUILD_FILE_NAME`` file in ``dir``.
+fn write_build_file(dir: &Path, deps_dir: PathBuf, graph: Graph<BuildUnit, ()>) -> Result<()> {
+    let sccs = petgraph::algo::kosaraju_scc(&graph);
+    let mut units: Vec<Option<BuildUnit>> = graph
+        .into_nodes_edges()
+        .0
+        .into_iter()
+        .map(|x| Some(x.weight))
+        .collect();
+    let sccs: Vec<Vec<BuildUnit>> = sccs
+        .iter()
+        .map(|x| x.iter().map(|y| units[y.index()].take().unwrap()).collect())
+        .collect();
     fs::write(
-        cfg_dir.join(BUILD_FILE_NAME),
-        serde_json::to_string(&sccs)
-            .with_context(|| format!("Failed with {BUILD_FILE_NAME} file."))?,
+        dir.join(BUILD_FILE_NAME),
+        serde_json::to_string(&BuildFile {
+            version: BUILD_FILE_VERSION,
+            deps_dir,
+            sccs,
+        })
+        .with_context(|| format!("Failed with {BUILD_FILE_NAME} file."))?,
     )?;
     Ok(())
 }
 
-#[allow(clippy::too_many_arguments)]
-fn install_h(
-    cfg_dir_name: OsString,
-    lock: Option<LockUnit>,
+/// Write the ``BUILD_FILE_NAME`` file of the member ``i`` and its transitive deps
+/// to the member dir. The member is the root there.
+fn write_member_build_file(
+    project_dir: &Path,
     deps_dir: &Path,
-    deps: Vec<Dep>,
-    options: &HashSet<String>,
-    installer: &impl Fn(&Path, &Path, &LockUnit) -> Result<()>,
-    prev_i: NodeIndex,
-    i_bimap: &mut BiMap<BuildUnit, NodeIndex>,
-    graph: &mut Graph<(), ()>,
-    locks: &mut Vec<LockUnit>,
-    existing_versions: &mut HashMap<String, Vec<(Version, String)>>,
+    graph: &Graph<BuildUnit, ()>,
+    i: NodeIndex,
 ) -> Result<()> {
-    let mut vec_for_name_map = Vec::with_capacity(deps.len());
-    let mut vec_to_trans_deps_install = Vec::with_capacity(deps.len());
-    for dep in deps {
-        if let Some(option_name) = dep.option_name {
-            if !options.contains(&option_name) {
+    let member_dir = PathBuf::from(&graph[i].dir);
+    let mut dfs = Dfs::new(graph, i);
+    let mut is = HashSet::new();
+    while let Some(j) = dfs.next(graph) {
+        is.insert(j);
+    }
+    let mut member_graph = graph.filter_map(
+        |j, unit| {
+            is.contains(&j).then(|| BuildUnit {
+                path: if j == i {
+                    PathBuf::from(".")
+                } else {
+                    unit_path(&member_dir, &project_dir.join(&unit.path))
+                },
+                root: j == i,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                ..unit.clone()
+            })
+        },
+        |_, ()| Some(()),
+    );
+    mark_unit_kinds(&mut member_graph);
+    write_build_file(
+        &member_dir,
+        unit_path(&member_dir, &project_dir.join(deps_dir)),
+        member_graph,
+    )
+}
+
+/// Push locks of ``units`` to ``locks``, ``dev_locks`` or ``group_locks`` of ``lock_file``.
+/// Units in subdirs of the same repo share a lock, so it's pushed once.
+fn fill_locks<'a>(lock_file: &mut LockFile, units: impl IntoIterator<Item = &'a BuildUnit>) {
+    let push = |locks: &mut Vec<LockUnit>, lock: &LockUnit| {
+        if !locks.contains(lock) {
+            locks.push(lock.clone());
+        }
+    };
+    for unit in units {
+        if let Some(lock) = &unit.lock {
+            if unit.dev {
+                push(&mut lock_file.dev_locks, lock);
+            } else if unit.groups.is_empty() {
+                push(&mut lock_file.locks, lock);
+            } else {
+                for group in &unit.groups {
+                    push(
+                        lock_file.group_locks.entry(group.clone()).or_default(),
+                        lock,
+                    );
+                }
+            }
+        }
+    }
+    lock_file.dev_locks.retain(|x| !lock_file.locks.contains(x));
+    for group_locks in lock_file.group_locks.values_mut() {
+        // A lock can serve both dev-only and group-only units, so it stays in both.
+        group_locks.retain(|x| !lock_file.locks.contains(x));
+    }
+    lock_file.group_locks.retain(|_, x| !x.is_empty());
+}
+
+/// Fill ``lock_file`` with locks of ``graph`` units, which is resolved with all targets.
+/// Locks of units, which are reached only through ``target_edges``, go to ``target_locks``
+/// of the targets.
+fn fill_target_locks(
+    lock_file: &mut LockFile,
+    mut graph: Graph<BuildUnit, ()>,
+    target_edges: &[(NodeIndex, NodeIndex, String)],
+) {
+    mark_unit_kinds(&mut graph);
+    // Deps with targets aren't in ``name_map``s, so only ``target_edges`` reach them.
+    let normal = reached(
+        &graph,
+        graph
+            .node_indices()
+            .filter(|x| graph[*x].root || graph[*x].member),
+    );
+    let untargeted: HashSet<NodeIndex> = graph
+        .node_indices()
+        .filter(|x| normal.contains(x) || graph[*x].dev || !graph[*x].groups.is_empty())
+        .collect();
+    fill_locks(lock_file, untargeted.iter().map(|x| &graph[*x]));
+    for (i, j, target) in target_edges {
+        if !untargeted.contains(i) {
+            continue;
+        }
+        let locks = lock_file.target_locks.entry(target.clone()).or_default();
+        let mut dfs = Dfs::new(&graph, *j);
+        while let Some(k) = dfs.next(&graph) {
+            if let Some(lock) = graph[k].lock.as_ref().filter(|_| !untargeted.contains(&k)) {
+                locks.push(lock.clone());
+            }
+        }
+    }
+    for locks in lock_file.target_locks.values_mut() {
+        locks.retain(|x| !lock_file.locks.contains(x));
+        locks.sort();
+        locks.dedup();
+    }
+    lock_file.target_locks.retain(|_, x| !x.is_empty());
+}
+
+/// A state of the ``install`` resolution.
+struct Resolver<'a, I> {
+    project_dir: &'a Path,
+    deps_dir: &'a Path,
+    installer: &'a I,
+    /// All targets match, if there are no host cfgs.
+    /// Such a resolution is for locks, so deps with targets aren't in ``name_map``s.
+    host_cfgs: Option<HashSet<(String, Option<String>)>>,
+    /// Canonical dirs of workspace members. Their dev deps are installed too.
+    member_dirs: BTreeSet<PathBuf>,
+    /// Repos to (version, commit)s of their tags.
+    existing_versions: HashMap<String, Vec<(Version, String)>>,
+    /// Dep dirs to options, which the deps are enabled with in the previous pass.
+    /// Units are resolved with them.
+    unit_options: HashMap<OsString, BTreeSet<String>>,
+    /// Dep dirs to options, which the deps are enabled with in the current pass.
+    dep_options: HashMap<OsString, BTreeSet<String>>,
+    dir_is: HashMap<OsString, NodeIndex>,
+    graph: Graph<BuildUnit, ()>,
+    /// (unit, dep, target)s of deps with targets.
+    target_edges: Vec<(NodeIndex, NodeIndex, String)>,
+    /// A dep with a target is met.
+    has_targets: bool,
+    requirements: Vec<Requirements>,
+    /// Unknown keys of cfgs of members are warnings instead of errors.
+    lenient: bool,
+    /// Warnings about unknown keys of cfgs.
+    warnings: Vec<String>,
+}
+
+impl<'a, I: Fn(&Path, &Path, &LockUnit) -> Result<()>> Resolver<'a, I> {
+    fn new(
+        project_dir: &'a Path,
+        deps_dir: &'a Path,
+        installer: &'a I,
+        host_cfgs: Option<HashSet<(String, Option<String>)>>,
+        member_dirs: BTreeSet<PathBuf>,
+        existing_versions: HashMap<String, Vec<(Version, String)>>,
+        lenient: bool,
+    ) -> Self {
+        Resolver {
+            project_dir,
+            deps_dir,
+            installer,
+            host_cfgs,
+            member_dirs,
+            existing_versions,
+            unit_options: HashMap::new(),
+            dep_options: HashMap::new(),
+            dir_is: HashMap::new(),
+            graph: Graph::new(),
+            target_edges: vec![],
+            has_targets: false,
+            requirements: vec![],
+            lenient,
+            warnings: vec![],
+        }
+    }
+
+    /// Add ``root`` with its transitive deps and members to ``graph``.
+    /// ``root_dep_options`` are dep names to options, which ``root`` enables on them.
+    fn resolve(
+        &mut self,
+        root: &BuildUnit,
+        root_dep_options: &BTreeMap<String, BTreeSet<String>>,
+        deps: &[Dep],
+        dev_deps: &[Dep],
+        group_deps: &[(String, Dep)],
+        root_requirements: &Requirements,
+    ) -> Result<()> {
+        // A pass resolves units with options of the previous pass, so it doesn't depend on
+        // the deps order. ``when``s with ``not(...)`` can disable deps, which enabled options,
+        // so options are rebuilt by every pass until they don't change.
+        let mut passed_options = vec![];
+        loop {
+            self.dep_options.clear();
+            self.dir_is.clear();
+            self.graph.clear();
+            self.target_edges.clear();
+            self.requirements = vec![root_requirements.clone()];
+            self.warnings.clear();
+            self.install_h(
+                root.clone(),
+                root_dep_options,
+                deps.to_vec(),
+                dev_deps.to_vec(),
+                group_deps.to_vec(),
+            )?;
+            for member_dir in self.member_dirs.clone() {
+                if !self.dir_is.contains_key(member_dir.as_os_str()) {
+                    let member_cfg = self.cfg(&member_dir)?;
+                    self.install_unit(member_dir.into_os_string(), None, None, member_cfg)?;
+                }
+            }
+            if self.dep_options == self.unit_options {
+                break;
+            }
+            ensure!(
+                !passed_options.contains(&self.dep_options),
+                "Options of deps don't converge because of not(...) in whens."
+            );
+            passed_options.push(std::mem::replace(
+                &mut self.unit_options,
+                std::mem::take(&mut self.dep_options),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Add ``build_unit`` and its not yet added transitive deps to ``graph``.
+    /// Return an index of ``build_unit``.
+    /// ``build_unit`` is without ``name_map``, ``dev_deps``, ``group_deps`` and ``dep_reqs``.
+    /// They are filled here.
+    /// ``group_deps`` are (group, dep)s.
+    /// ``forwarded_options`` are dep names to options, which ``build_unit`` enables on them.
+    fn install_h(
+        &mut self,
+        mut build_unit: BuildUnit,
+        forwarded_options: &BTreeMap<String, BTreeSet<String>>,
+        deps: Vec<Dep>,
+        dev_deps: Vec<Dep>,
+        group_deps: Vec<(String, Dep)>,
+    ) -> Result<NodeIndex> {
+        let ndeps = deps.len() + dev_deps.len() + group_deps.len();
+        let mut vec_for_name_map = Vec::with_capacity(ndeps);
+        let mut vec_to_trans_deps_install = Vec::with_capacity(ndeps);
+        let mut unmapped_names = vec![];
+        for (dep, is_dev, group) in deps
+            .into_iter()
+            .map(|x| (x, false, None))
+            .chain(dev_deps.into_iter().map(|x| (x, true, None)))
+            .chain(group_deps.into_iter().map(|x| (x.1, false, Some(x.0))))
+        {
+            if !is_enabled(&dep, &build_unit.options)? || !self.is_target_matched(&dep)? {
+                unmapped_names.push(dep.name);
                 continue;
             }
+            let dep_req = DepReq {
+                option_name: dep.option_name,
+                when: dep.when,
+                version: match &dep.dep_type {
+                    Some(DepType::Version(version)) => Some(version.clone()),
+                    _ => None,
+                },
+            };
+            let (dep_dir_name, dep_lock, version) = if let Some(path) = dep.path {
+                ensure!(
+                    build_unit.lock.is_none(),
+                    "{path:#?} path dep isn't allowed in {:?} git dep cfg.",
+                    build_unit.dir
+                );
+                let dir = fs::canonicalize(self.project_dir.join(&build_unit.path).join(&path))
+                    .with_context(|| {
+                        format!(
+                            "Failed with {path:#?} path dep in {:?} cfg.",
+                            build_unit.dir
+                        )
+                    })?;
+                (dir.into_os_string(), None, None)
+            } else {
+                let (dep_lock, version) =
+                    dep_lock(dep.repo.unwrap(), dep.dep_type, &mut self.existing_versions)?;
+                let dep_dir_name = dep_dir(&dep_lock)?;
+                let dep_dir_path = self.deps_dir.join(&dep_dir_name);
+                if !Path::new(&dep_dir_path).exists() {
+                    (self.installer)(self.deps_dir, &dep_dir_path, &dep_lock).with_context(
+                        || format!("Failed with {dep_lock:?} in {:?} cfg.", build_unit.dir),
+                    )?;
+                }
+                let dep_dir_name = match dep.subdir {
+                    // Components make one dir name of equal subdirs, like ``a/b/`` and ``a/./b``.
+                    Some(subdir) => Path::new(&dep_dir_name)
+                        .components()
+                        .chain(subdir.components())
+                        .collect::<PathBuf>()
+                        .into_os_string(),
+                    None => dep_dir_name,
+                };
+                (dep_dir_name, Some(dep_lock), version)
+            };
+            let dep_cfg = self.cfg(&self.deps_dir.join(&dep_dir_name))?;
+            let dep_name = dep.name.unwrap_or(dep_cfg.name.clone());
+            let options = self.dep_options.entry(dep_dir_name.clone()).or_default();
+            options.extend(dep.options.unwrap_or(vec![]));
+            if let Some(forwarded) = forwarded_options.get(&dep_name) {
+                options.extend(forwarded.iter().cloned());
+            }
+            if self.host_cfgs.is_none() && dep.target.is_some() {
+                // Deps of different targets can have the same name.
+                unmapped_names.push(Some(dep_name));
+            } else {
+                vec_for_name_map.push((dep_name, dep_dir_name.clone(), is_dev, group, dep_req));
+            }
+            vec_to_trans_deps_install.push((dep_dir_name, dep_lock, version, dep_cfg, dep.target));
+        }
+        fill_name_map(
+            &mut build_unit,
+            vec_for_name_map,
+            forwarded_options,
+            &unmapped_names,
+        )?;
+        let dir = build_unit.dir.clone();
+        let i = self.graph.add_node(build_unit);
+        self.dir_is.insert(dir, i);
+        for (dep_dir_name, dep_lock, version, dep_cfg, target) in vec_to_trans_deps_install {
+            let dep_i = if let Some(dep_i) = self.dir_is.get(&dep_dir_name) {
+                *dep_i
+            } else {
+                self.install_unit(dep_dir_name, dep_lock, version, dep_cfg)?
+            };
+            self.graph.update_edge(i, dep_i, ());
+            if let Some(target) = target {
+                self.target_edges.push((i, dep_i, target));
+            }
         }
-        let dep_lock = LockUnit {
-            lock_type: match dep
-                .dep_type
-                .unwrap_or(DepType::Branch("default".to_string()))
-            {
-                DepType::Version(version) => LockType::Commit(
-                    existing_versions
-                        .entry(dep.repo.clone())
-                        .or_insert(version_tags(&dep.repo)?)
-                        .iter()
-                        .rev()
-                        .find(|x| version.matches(&x.0))
-                        .with_context(|| format!("There is no {version:?} in {}.", dep.repo))?
-                        .1
-                        .clone(),
-                ),
-                DepType::Branch(branch) => LockType::Branch(branch),
-                DepType::Commit(commit) => LockType::Commit(commit),
+        Ok(i)
+    }
+
+    /// Add the dep unit in ``dir`` and its not yet added transitive deps to ``graph``.
+    /// ``dir`` is a dir name in ``deps_dir`` or an absolute path of a path dep.
+    fn install_unit(
+        &mut self,
+        dir: OsString,
+        lock: Option<LockUnit>,
+        version: Option<Version>,
+        cfg: Cfg,
+    ) -> Result<NodeIndex> {
+        self.requirements.push(cfg.requirements());
+        let dir_path = self.deps_dir.join(&dir);
+        let member = self.member_dirs.contains(&dir_path);
+        let options = resolve_options(
+            &cfg.options,
+            &[cfg.deps.as_slice(), cfg.dev_deps.as_slice()].concat(),
+            self.unit_options.get(&dir).into_iter().flatten().cloned(),
+        )
+        .with_context(|| format!("Failed with {dir:?} cfg options."))?;
+        let path = if lock.is_some() {
+            unit_path(self.project_dir, &dir_path)
+        } else {
+            unit_path(&fs::canonicalize(self.project_dir)?, &dir_path)
+        };
+        self.install_h(
+            BuildUnit {
+                dir,
+                name: cfg.name,
+                path,
+                root: false,
+                member,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock,
+                version,
+                options: options.options,
+                name_map: BTreeMap::new(),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new(),
             },
-            repo: dep.repo,
+            &options.dep_options,
+            cfg.deps,
+            if member { cfg.dev_deps } else { vec![] },
+            vec![],
+        )
+    }
+
+    /// Read the cfg in ``dir``. Unknown keys are errors only for members, unless ``lenient``.
+    /// Invalid package metadata is an error only for members.
+    fn cfg(&mut self, dir: &Path) -> Result<Cfg> {
+        let (cfg, warnings) = if self.member_dirs.contains(dir) {
+            Cfg::with_warnings(dir, self.lenient)?
+        } else {
+            Cfg::of_dep(dir)?
         };
-        let dep_dir_name = dep_dir(&dep_lock)?;
-        let dep_dir_path = deps_dir.join(&dep_dir_name);
-        if !Path::new(&dep_dir_path).exists() {
-            installer(deps_dir, &dep_dir_path, &dep_lock)
-                .with_context(|| format!("Failed with {dep_lock:?} in {cfg_dir_name:?} cfg."))?;
-        }
-        let dep_cfg = Cfg::new(&dep_dir_path)?;
-        vec_for_name_map.push((dep.name.unwrap_or(dep_cfg.name), dep_dir_name.clone()));
-        vec_to_trans_deps_install.push((dep_cfg.deps, dep_dir_name, dep.options, dep_lock));
-    }
-    let mut name_map = BTreeMap::new();
-    for (dep_name, dep_dir) in vec_for_name_map {
-        ensure!(
-            !name_map.contains_key(&dep_name),
-            "Two equal names of deps ({dep_name:?}) exist in {cfg_dir_name:?} cfg."
-        );
-        name_map.insert(dep_name, dep_dir);
+        for warning in warnings {
+            if !self.warnings.contains(&warning) {
+                self.warnings.push(warning);
+            }
+        }
+        Ok(cfg)
+    }
+
+    /// Return whether the target of ``dep`` matches ``host_cfgs``.
+    fn is_target_matched(&mut self, dep: &Dep) -> Result<bool> {
+        let Some(target) = &dep.target else {
+            return Ok(true);
+        };
+        self.has_targets = true;
+        Ok(match &self.host_cfgs {
+            Some(host_cfgs) => expr::Expr::parse_cfg(target)?.eval_cfgs(host_cfgs),
+            None => true,
+        })
+    }
+}
+
+/// Return whether ``option_name`` and ``when`` of ``dep`` are satisfied by ``options``.
+fn is_enabled(dep: &Dep, options: &BTreeSet<String>) -> Result<bool> {
+    if dep
+        .option_name
+        .as_ref()
+        .is_some_and(|x| !options.contains(x))
+    {
+        return Ok(false);
     }
-    let build_unit = BuildUnit {
-        dir: cfg_dir_name,
-        name_map,
+    let Some(when) = &dep.when else {
+        return Ok(true);
     };
-    let (i, contains_edge) = if i_bimap.contains_left(&build_unit) {
-        let i = *i_bimap.get_by_left(&build_unit).unwrap();
-        (i, graph.contains_edge(prev_i, i))
-    } else {
-        let i = graph.add_node(());
-        i_bimap.insert(build_unit, i);
-        if let Some(lock) = lock {
-            locks.push(lock);
+    Ok(expr::Expr::parse(when)?.eval(&|name, _| options.contains(name)))
+}
+
+/// Return deps of the unit ``i`` with ``names``.
+fn dep_is(graph: &Graph<BuildUnit, ()>, i: NodeIndex, names: &BTreeSet<String>) -> Vec<NodeIndex> {
+    let dirs: HashSet<&OsString> = names.iter().map(|x| &graph[i].name_map[x]).collect();
+    graph
+        .neighbors(i)
+        .filter(|x| dirs.contains(&graph[*x].dir))
+        .collect()
+}
+
+/// Return units, which are reached from ``starts`` not through dev deps and groups.
+fn reached(
+    graph: &Graph<BuildUnit, ()>,
+    starts: impl IntoIterator<Item = NodeIndex>,
+) -> HashSet<NodeIndex> {
+    let mut reached = HashSet::new();
+    let mut stack: Vec<NodeIndex> = starts.into_iter().collect();
+    while let Some(i) = stack.pop() {
+        if reached.insert(i) {
+            let unit = &graph[i];
+            let normal_names: BTreeSet<String> = unit
+                .name_map
+                .keys()
+                .filter(|x| {
+                    !unit.dev_deps.contains(*x) && !unit.group_deps.values().any(|y| y.contains(*x))
+                })
+                .cloned()
+                .collect();
+            stack.extend(dep_is(graph, i, &normal_names));
         }
-        (i, false)
-    };
-    if !contains_edge {
-        graph.add_edge(prev_i, i, ());
-        for (dep_deps, dep_dir_name, options, lock) in vec_to_trans_deps_install {
-            install_h(
-                dep_dir_name,
-                Some(lock),
-                deps_dir,
-                dep_deps,
-                &options.unwrap_or(vec![]).into_iter().collect(),
-                installer,
-                i,
-                i_bimap,
-                graph,
-                locks,
-                existing_versions,
-            )?;
+    }
+    reached
+}
+
+/// Set ``dev`` of units, which are reached only through dev deps of the root or members,
+/// and ``groups`` of units, which are reached only through groups of the root.
+fn mark_unit_kinds(graph: &mut Graph<BuildUnit, ()>) {
+    let normal = reached(
+        graph,
+        graph
+            .node_indices()
+            .filter(|x| graph[*x].root || graph[*x].member),
+    );
+    let dev = reached(
+        graph,
+        normal
+            .iter()
+            .flat_map(|x| dep_is(graph, *x, &graph[*x].dev_deps)),
+    );
+    let groups: Vec<(String, HashSet<NodeIndex>)> = normal
+        .iter()
+        .flat_map(|x| {
+            graph[*x]
+                .group_deps
+                .iter()
+                .map(|(group, names)| (group.clone(), reached(graph, dep_is(graph, *x, names))))
+        })
+        .collect();
+    for i in graph.node_indices() {
+        if normal.contains(&i) {
+            continue;
+        }
+        if dev.contains(&i) {
+            graph[i].dev = true;
+            continue;
+        }
+        for (group, reached) in &groups {
+            if reached.contains(&i) {
+                graph[i].groups.insert(group.clone());
+            }
         }
     }
+}
+
+/// Fill ``name_map``, ``dev_deps``, ``group_deps`` and ``dep_reqs`` of ``build_unit`` with
+/// (dep name, dep dir, is dev, group, dep req)s.
+/// ``forwarded_options`` of deps, which aren't in ``name_map``, like disabled ones,
+/// are skipped. ``unmapped_names`` are their names. Package names of disabled deps are
+/// unknown, so a disabled dep without ``name`` can be any dep.
+fn fill_name_map(
+    build_unit: &mut BuildUnit,
+    vec_for_name_map: Vec<(String, OsString, bool, Option<String>, DepReq)>,
+    forwarded_options: &BTreeMap<String, BTreeSet<String>>,
+    unmapped_names: &[Option<String>],
+) -> Result<()> {
+    for (dep_name, dep_dir, is_dev, group, dep_req) in vec_for_name_map {
+        ensure!(
+            !build_unit.name_map.contains_key(&dep_name),
+            "Two equal names of deps ({dep_name:?}) exist in {:?} cfg.",
+            build_unit.dir
+        );
+        if is_dev {
+            build_unit.dev_deps.insert(dep_name.clone());
+        }
+        if let Some(group) = group {
+            build_unit
+                .group_deps
+                .entry(group)
+                .or_default()
+                .insert(dep_name.clone());
+        }
+        if dep_req.option_name.is_some() || dep_req.when.is_some() || dep_req.version.is_some() {
+            build_unit.dep_reqs.insert(dep_name.clone(), dep_req);
+        }
+        build_unit.name_map.insert(dep_name, dep_dir);
+    }
+    for dep_name in forwarded_options.keys() {
+        ensure!(
+            build_unit.name_map.contains_key(dep_name)
+                || unmapped_names
+                    .iter()
+                    .any(|x| x.as_ref().is_none_or(|x| x == dep_name)),
+            "There is no {dep_name:?} dep for its options in {:?} cfg.",
+            build_unit.dir
+        );
+    }
     Ok(())
 }
 
-/// Return a vec of (version, commit).
-fn version_tags(repo: &str) -> Result<Vec<(Version, String)>> {
+/// Return a lock and a version of the tag, which the commit was selected by.
+fn dep_lock(
+    repo: String,
+    dep_type: Option<DepType>,
+    existing_versions: &mut HashMap<String, Vec<(Version, String)>>,
+) -> Result<(LockUnit, Option<Version>)> {
+    let (lock_type, version) = match dep_type.unwrap_or(DepType::Branch("default".to_string())) {
+        DepType::Version(version_req) => {
+            let (version, commit) = existing_versions
+                .entry(repo.clone())
+                .or_insert(version_tags(&repo)?)
+                .iter()
+                .rev()
+                .find(|x| version_req.matches(&x.0))
+                .with_context(|| format!("There is no {version_req:?} in {repo}."))?
+                .clone();
+            (LockType::Commit(commit), Some(version))
+        }
+        DepType::Branch(branch) => (LockType::Branch(branch), None),
+        DepType::Commit(commit) => (LockType::Commit(commit), None),
+    };
+    Ok((LockUnit { repo, lock_type }, version))
+}
+
+fn unit_path(project_dir: &Path, dir: &Path) -> PathBuf {
+    dir.strip_prefix(project_dir)
+        .map_or_else(|_| dir.to_path_buf(), Path::to_path_buf)
+}
+
+/// Return a vec of (version, commit) of version tags of ``repo`` sorted by versions.
+pub fn version_tags(repo: &str) -> Result<Vec<(Version, String)>> {
     let mut v: Vec<(Version, String)> = std::str::from_utf8(
         &Command::new("git")
             .arg("ls-remote")
@@ -299,6 +1367,27 @@ fn version_tags(repo: &str) -> Result<Vec<(Version, String)>> {
     Ok(v)
 }
 
+/// Return the newest commit of ``branch`` of ``repo``.
+/// The ``default`` branch is the git default one.
+pub fn branch_commit(repo: &str, branch: &str) -> Result<String> {
+    let output = Command::new("git")
+        .arg("ls-remote")
+        .arg(repo)
+        .arg(if branch == "default" {
+            "HEAD".to_string()
+        } else {
+            format!("refs/heads/{branch}")
+        })
+        .output()
+        .with_context(|| format!("Failed with {repo:?} repo."))?;
+    with_stderr(&output).with_context(|| format!("Failed with {repo:?} repo."))?;
+    std::str::from_utf8(&output.stdout)?
+        .split_whitespace()
+        .next()
+        .map(str::to_string)
+        .with_context(|| format!("There is no {branch:?} branch in {repo:?} repo."))
+}
+
 /// Install deps from remote repos.
 pub fn net_installer(deps_dir: &Path, dep_dir_path: &Path, lock: &LockUnit) -> Result<()> {
     match lock.lock_type {
@@ -352,7 +1441,7 @@ pub fn net_installer(deps_dir: &Path, dep_dir_path: &Path, lock: &LockUnit) -> R
             }
             with_stderr(&command.arg(dep_dir_path).output()?)?;
         }
-    };
+    }
     Ok(())
 }
 
@@ -365,7 +1454,20 @@ pub fn with_stderr(output: &std::process::Output) -> Result<()> {
 }
 
 /// Delete deps dirs, which aren't in the ``LOCK_FILE_NAME`` file.
-pub fn clean(locks: &[LockUnit], deps_dir: &Path, buffer: &mut impl std::io::Write) -> Result<()> {
+/// Fail, if ``deps_dir`` isn't inside ``project_dir``, because other projects can share it.
+pub fn clean(
+    locks: &[LockUnit],
+    project_dir: &Path,
+    deps_dir: &Path,
+    buffer: &mut impl std::io::Write,
+) -> Result<()> {
+    let normalized_deps_dir = normalized(deps_dir)?;
+    let normalized_project_dir = normalized(project_dir)?;
+    ensure!(
+        normalized_deps_dir.starts_with(&normalized_project_dir)
+            && normalized_deps_dir != normalized_project_dir,
+        "{deps_dir:#?} deps dir isn't inside {project_dir:#?} project dir, so other projects can use it. Delete its dirs manually."
+    );
     let locked_dep_dirs = locks
         .iter()
         .map(dep_dir)
@@ -396,7 +1498,7 @@ pub fn dep_dir(lock: &LockUnit) -> Result<OsString> {
             let mut dir = OsString::from(repo_author_and_name(&lock.repo)?);
             dir.push(".branch");
             dir.push(".");
-            dir.push(&branch.clone());
+            dir.push(branch);
             Ok(dir)
         }
     }
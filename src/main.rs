@@ -2,25 +2,45 @@ use anyhow::{Context, Result};
 use clap::{CommandFactory, Parser};
 use crack::with_stderr;
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap, HashSet},
     fs,
-    io::{stdout, Write},
+    io::stdout,
     path::{Path, PathBuf},
     process::Command,
 };
 
+/// The env var, which is the same as ``--lenient`` flag.
+const LENIENT_VAR: &str = "CRACK_LENIENT";
+
 #[derive(clap::Parser)]
 #[command(about = "A Sanskrit package manager", long_about = None)]
 pub struct Cli {
     #[command(subcommand)]
     pub subcommand: Subcommand,
+    /// The deps directory instead of the crack.toml or env one
+    #[clap(long, global = true)]
+    pub deps_dir: Option<PathBuf>,
+    /// Warn about unknown crack.toml keys instead of failing
+    #[clap(long, global = true)]
+    pub lenient: bool,
 }
 
 #[derive(clap::Subcommand)]
 pub enum Subcommand {
     /// Install crack.toml deps, which aren't in the deps directory, and produce crack.build.
     #[clap(visible_alias = "i")]
-    Install { options: Option<Vec<String>> },
+    Install {
+        options: Option<Vec<String>>,
+        /// Skip dev deps
+        #[clap(long)]
+        no_dev: bool,
+        /// Install deps of the group too
+        #[clap(short, long)]
+        group: Vec<String>,
+        /// Enable the cfg (a name or key=value) for targets
+        #[clap(long)]
+        cfg: Vec<String>,
+    },
     /// Update deps, which are in crack.lock.
     #[clap(visible_alias = "u")]
     Update,
@@ -29,10 +49,29 @@ pub enum Subcommand {
     UpdateRegistry,
     /// Delete directories, which aren't in crack.lock.
     #[clap(visible_alias = "c")]
-    Clean,
-    /// Create an empty project
+    Clean {
+        /// Delete dev-only deps too
+        #[clap(long)]
+        no_dev: bool,
+    },
+    /// Create a project with a source skeleton, .gitignore and a git repo
     #[clap(visible_alias = "n")]
-    New { project_name: std::ffi::OsString },
+    New {
+        project_name: PathBuf,
+        #[clap(flatten)]
+        project: ProjectArgs,
+        /// A git url or a dir of a template. {{name}} is substituted with the project name
+        #[clap(short, long, conflicts_with_all = ["lib", "bin", "interpreter"])]
+        template: Option<String>,
+    },
+    /// Make the current directory a project like new does. Existing files are left alone
+    Init {
+        /// A project name instead of the directory one
+        #[clap(long)]
+        name: Option<String>,
+        #[clap(flatten)]
+        project: ProjectArgs,
+    },
     /// Build the project
     #[clap(visible_alias = "b")]
     Build {
@@ -40,6 +79,9 @@ pub enum Subcommand {
         interpreter: Option<PathBuf>,
         #[clap(short, long)]
         build_file: Option<PathBuf>,
+        /// Build the workspace member
+        #[clap(short, long)]
+        package: Option<String>,
     },
     /// Run the project program
     #[clap(visible_alias = "r")]
@@ -48,17 +90,161 @@ pub enum Subcommand {
         interpreter: Option<PathBuf>,
         #[clap(short, long)]
         build_file: Option<PathBuf>,
+        /// Run the workspace member
+        #[clap(short, long)]
+        package: Option<String>,
     },
-    /// Add a dep to crack.toml.
+    /// Add a dep to crack.toml and install deps.
     #[clap(visible_alias = "a")]
-    Add { dep_name: String },
-    /// Add a dev-dep to crack.toml.
+    Add(AddArgs),
+    /// Add a dev-dep to crack.toml and install deps.
     #[clap(visible_alias = "ad")]
-    AddDev { dev_dep_name: String },
+    AddDev(AddArgs),
+    /// Remove deps from crack.toml by names, registry names, repos, paths or package names
+    /// and install deps.
+    #[clap(visible_alias = "rm")]
+    Remove {
+        #[clap(required = true)]
+        dep_names: Vec<String>,
+        /// Remove dev-deps
+        #[clap(long)]
+        dev: bool,
+        /// Delete directories, which aren't in crack.lock, after that
+        #[clap(long)]
+        clean: bool,
+    },
+    /// Set version requirements of deps to their latest version tags and install deps.
+    Upgrade {
+        /// Names or registry names of deps. All deps are upgraded without them
+        dep_names: Vec<String>,
+        /// Stay within the current requirements
+        #[clap(long)]
+        compatible: bool,
+        /// Only print upgrades
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Print locked deps with their newest branch commits and version tags.
+    #[clap(visible_alias = "o")]
+    Outdated {
+        #[clap(short, long, default_value = "table")]
+        format: OutdatedFormat,
+    },
+    /// Print the deps tree from crack.build.
+    #[clap(visible_alias = "t")]
+    Tree {
+        /// Max depth of shown deps
+        #[clap(short, long)]
+        depth: Option<usize>,
+        /// Show units, which depend on the dep (a package name, a dir or a repo)
+        #[clap(short, long)]
+        invert: Option<String>,
+        /// Show only deps with the same repo, but different locks
+        #[clap(short = 'D', long)]
+        duplicates: bool,
+        /// Show only deps, which are reached through root deps of the kind
+        #[clap(short, long)]
+        edges: Option<Edges>,
+    },
+    /// Print all paths from the root to the dep (a package name, a dir or a repo).
+    #[clap(visible_alias = "w")]
+    Why { dep: String },
+    /// Export the deps graph from crack.build.
+    #[clap(visible_alias = "g")]
+    Graph {
+        #[clap(short, long, default_value = "dot")]
+        format: GraphFormat,
+    },
+    /// Check crack.toml of the project and its workspace members.
+    #[clap(visible_alias = "cm")]
+    CheckManifest,
     /// Generate completion
     Completion { shell: clap_complete::Shell },
 }
 
+#[derive(clap::Args)]
+pub struct AddArgs {
+    /// A registry name of the dep
+    #[clap(required_unless_present = "git", conflicts_with = "git")]
+    dep_name: Option<String>,
+    /// A repo of the dep, which isn't in the registry
+    #[clap(long)]
+    git: Option<String>,
+    #[clap(long, conflicts_with_all = ["commit", "version"])]
+    branch: Option<String>,
+    #[clap(long, conflicts_with = "version")]
+    commit: Option<String>,
+    /// A version requirement or "latest" for a caret one on the latest version tag
+    #[clap(
+        long,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "latest"
+    )]
+    version: Option<String>,
+    /// A dep name, which is used in the code
+    #[clap(long)]
+    rename: Option<String>,
+    /// An option, which enables the dep
+    #[clap(long)]
+    optional: Option<String>,
+    /// Options of the dep
+    #[clap(long, value_delimiter = ',')]
+    options: Vec<String>,
+    /// Replace the dep with the same name or repo
+    #[clap(short, long)]
+    update: bool,
+}
+
+#[derive(clap::Args)]
+pub struct ProjectArgs {
+    /// Create src/lib.sk
+    #[clap(long, conflicts_with = "bin")]
+    lib: bool,
+    /// Create src/main.sk. It's the default
+    #[clap(long)]
+    bin: bool,
+    #[clap(short, long)]
+    interpreter: Option<PathBuf>,
+}
+
+impl ProjectArgs {
+    fn kind(&self) -> crack::project::ProjectKind {
+        if self.lib {
+            crack::project::ProjectKind::Lib
+        } else {
+            crack::project::ProjectKind::Bin
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum GraphFormat {
+    Dot,
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum OutdatedFormat {
+    Table,
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum Edges {
+    Normal,
+    Dev,
+}
+
+impl From<Edges> for crack::graph::DepKind {
+    fn from(edges: Edges) -> Self {
+        match edges {
+            Edges::Normal => crack::graph::DepKind::Normal,
+            Edges::Dev => crack::graph::DepKind::Dev,
+        }
+    }
+}
+
 fn registry() -> Result<HashMap<String, String>> {
     Ok(toml::from_str(&fs::read_to_string(
         Path::new(&std::env::var("HOME")?)
@@ -80,24 +266,93 @@ fn project_root() -> Result<PathBuf> {
         })
 }
 
-// WARN: hardcoded "deps"
-fn add(dep_name: &str, dev_deps: bool) -> Result<()> {
-    fs::OpenOptions::new()
-        .append(true)
-        .open(project_root()?.join(crack::CFG_FILE_NAME))?
-        .write_all(
-            (format!("\n\n[[{}deps]]\n", if dev_deps { "dev_" } else { "" })
-                + &toml::to_string(&crack::Dep {
-                    name: None,
-                    repo: registry()?
-                        .remove(dep_name)
-                        .with_context(|| format!("There is no {dep_name} in the registry."))?,
-                    dep_type: None,
-                    options: None,
-                    option_name: None,
-                })?)
-                .as_bytes(),
-        )?;
+fn add(args: AddArgs, dev_deps: bool, deps_dir: Option<PathBuf>, lenient: bool) -> Result<()> {
+    let repo = if let Some(repo) = args.git {
+        repo
+    } else {
+        let dep_name = args.dep_name.unwrap_or_default();
+        registry()?
+            .remove(&dep_name)
+            .with_context(|| format!("There is no {dep_name} in the registry."))?
+    };
+    let dep = crack::manifest::new_dep(
+        crack::manifest::NewDep {
+            repo,
+            branch: args.branch,
+            commit: args.commit,
+            version: args.version,
+            rename: args.rename,
+            optional: args.optional,
+            options: args.options,
+        },
+        &crack::version_tags,
+    )?;
+    crack::manifest::add_dep(&project_root()?, &dep, dev_deps, args.update)?;
+    reinstall(deps_dir, lenient)
+}
+
+/// Install deps with options, cfgs, groups and ``--no-dev`` of the last install.
+fn reinstall(deps_dir: Option<PathBuf>, lenient: bool) -> Result<()> {
+    let lock_file = crack::LockFile::new(&crack::workspace_root(&project_root()?)?)?;
+    install(
+        deps_dir,
+        &lock_file.root_options,
+        &lock_file.root_cfgs,
+        lock_file.root_no_dev,
+        &lock_file.root_groups.into_keys().collect(),
+        lenient,
+    )
+}
+
+fn clean(deps_dir: Option<PathBuf>, no_dev: bool) -> Result<()> {
+    let project_root = crack::workspace_root(&project_root()?)?;
+    let deps_dir = crack::deps_dir(&project_root, deps_dir)?;
+    if deps_dir.exists() {
+        fs::create_dir_all(&deps_dir)?;
+        let mut lock_file = crack::LockFile::new(&project_root)?;
+        if !no_dev {
+            lock_file.locks.append(&mut lock_file.dev_locks);
+        }
+        lock_file
+            .locks
+            .extend(lock_file.group_locks.into_values().flatten());
+        lock_file
+            .locks
+            .extend(lock_file.target_locks.into_values().flatten());
+        crack::clean(&lock_file.locks, &project_root, &deps_dir, &mut stdout())?;
+    } else {
+        println!("There is nothing to clean. {deps_dir:#?} directory doesn't exist.");
+    }
+    Ok(())
+}
+
+fn install(
+    deps_dir: Option<PathBuf>,
+    options: &HashSet<String>,
+    cfgs: &HashSet<String>,
+    no_dev: bool,
+    groups: &BTreeSet<String>,
+    lenient: bool,
+) -> Result<()> {
+    let project_root = crack::workspace_root(&project_root()?)?;
+    let deps_dir = crack::deps_dir(&project_root, deps_dir)?;
+    let warnings = crack::cfg_install(
+        &project_root,
+        &deps_dir,
+        options,
+        cfgs,
+        no_dev,
+        groups,
+        lenient,
+        &|deps_dir, dep_dir_path, lock| {
+            crack::net_installer(deps_dir, dep_dir_path, lock)?;
+            println!("{lock:?} was installed.");
+            Ok(())
+        },
+    )?;
+    for warning in warnings {
+        eprintln!("{warning}");
+    }
     Ok(())
 }
 
@@ -110,24 +365,42 @@ fn build_or_run(
     build_or_run: &BuildOrRun,
     interpreter: Option<PathBuf>,
     build_file: Option<PathBuf>,
+    package: Option<String>,
+    deps_dir: Option<PathBuf>,
+    lenient: bool,
 ) -> Result<()> {
-    let project_root = project_root()?;
+    let mut project_root = project_root()?;
+    let workspace_root = crack::workspace_root(&project_root)?;
+    if let Some(package) = package {
+        project_root = crack::member_dir(&workspace_root, &package)?;
+    }
     let interpreter = interpreter.map_or_else(
-        || -> Result<PathBuf> { Ok(crack::Cfg::new(&project_root)?.interpreter) },
+        || -> Result<PathBuf> {
+            Ok(crack::Cfg::with_warnings(&project_root, lenient)?
+                .0
+                .interpreter)
+        },
         Ok,
     )?;
     anyhow::ensure!(
         interpreter.is_absolute(),
         "The interpreter path must be absolute."
     );
+    let build_file = build_file.unwrap_or_else(|| project_root.join(crack::BUILD_FILE_NAME));
+    crack::check_requirements(
+        &crack::BuildFile::from_path(&build_file)?
+            .requirements(build_file.parent().unwrap_or(Path::new(".")))?,
+        &interpreter,
+    )?;
     let mut command = Command::new(interpreter);
-    command.current_dir(&project_root);
+    command.current_dir(&project_root).env(
+        crack::DEPS_DIR_VAR,
+        crack::deps_dir(&workspace_root, deps_dir)?,
+    );
     if let BuildOrRun::Build = build_or_run {
         command.arg("--check");
     }
-    let output = command
-        .arg(build_file.unwrap_or_else(|| project_root.join(crack::BUILD_FILE_NAME)))
-        .output()?;
+    let output = command.arg(build_file).output()?;
     with_stderr(&output)?;
     println!("{}", std::str::from_utf8(&output.stdout)?);
     Ok(())
@@ -136,26 +409,33 @@ fn build_or_run(
 #[allow(clippy::too_many_lines)]
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    let deps_dir = cli.deps_dir.map(std::path::absolute).transpose()?;
+    let lenient = cli.lenient || std::env::var_os(LENIENT_VAR).is_some();
     match cli.subcommand {
-        Subcommand::Install { options } => {
-            let project_root = project_root()?;
-            let deps_dir = project_root.join("deps");
-            crack::cfg_install(
-                &project_root,
-                &deps_dir,
-                &options.unwrap_or(vec![]).into_iter().collect(),
-                &|deps_dir, dep_dir_path, lock| {
-                    crack::net_installer(deps_dir, dep_dir_path, lock)?;
-                    println!("{lock:?} was installed.");
-                    Ok(())
-                },
-            )?;
-        }
+        Subcommand::Install {
+            options,
+            no_dev,
+            group,
+            cfg,
+        } => install(
+            deps_dir,
+            &options.unwrap_or(vec![]).into_iter().collect(),
+            &cfg.into_iter().collect(),
+            no_dev,
+            &group.into_iter().collect(),
+            lenient,
+        )?,
         Subcommand::Update => {
-            let project_root = project_root()?;
-            let deps_dir = project_root.join("deps");
+            let project_root = crack::workspace_root(&project_root()?)?;
+            let deps_dir = crack::deps_dir(&project_root, deps_dir)?;
             let lock_file = crack::LockFile::new(&project_root)?;
-            for lock in &lock_file.locks {
+            for lock in lock_file
+                .locks
+                .iter()
+                .chain(&lock_file.dev_locks)
+                .chain(lock_file.group_locks.values().flatten())
+                .chain(lock_file.target_locks.values().flatten())
+            {
                 if let crack::LockType::Branch(..) = lock.lock_type {
                     let dir = deps_dir.join(crack::dep_dir(lock)?);
                     crack::with_stderr(
@@ -181,50 +461,137 @@ fn main() -> Result<()> {
                     println!("{lock:?} was processed.");
                 }
             }
-            crack::install(
+            let warnings = crack::install(
                 &project_root,
                 &deps_dir,
                 lock_file.root_deps,
+                (!lock_file.root_no_dev).then_some(lock_file.root_dev_deps),
+                lock_file.root_groups,
                 &lock_file.root_options,
+                &lock_file.root_cfgs,
+                lenient,
                 &|deps_dir, dep_dir_path, lock| {
                     crack::net_installer(deps_dir, dep_dir_path, lock)?;
                     println!("{lock:?} was installed.");
                     Ok(())
                 },
             )?;
+            for warning in warnings {
+                eprintln!("{warning}");
+            }
         }
-        Subcommand::Clean => {
-            let project_root = project_root()?;
-            let deps_dir = project_root.join("deps");
-            if deps_dir.exists() {
-                fs::create_dir_all(&deps_dir)?;
-                crack::clean(
-                    &crack::LockFile::new(&project_root)?.locks,
-                    &deps_dir,
-                    &mut stdout(),
-                )?;
-            } else {
-                println!("There is nothing to clean. {deps_dir:#?} directory doesn't exist.");
+        Subcommand::Clean { no_dev } => clean(deps_dir, no_dev)?,
+        Subcommand::Remove {
+            dep_names,
+            dev,
+            clean: is_cleaned,
+        } => {
+            crack::manifest::remove_deps(
+                &project_root()?,
+                &dep_names,
+                &registry().unwrap_or_default(),
+                dev,
+            )?;
+            reinstall(deps_dir.clone(), lenient)?;
+            if is_cleaned {
+                clean(deps_dir, false)?;
+            }
+        }
+        Subcommand::Upgrade {
+            dep_names,
+            compatible,
+            dry_run,
+        } => {
+            let upgrades = crack::manifest::upgrade_deps(
+                &project_root()?,
+                &dep_names,
+                &registry().unwrap_or_default(),
+                compatible,
+                dry_run,
+                &crack::version_tags,
+            )?;
+            for upgrade in &upgrades {
+                println!("{}: {} -> {}", upgrade.dep, upgrade.from, upgrade.to);
+            }
+            if upgrades.is_empty() {
+                println!("Deps are up to date.");
+            } else if !dry_run {
+                reinstall(deps_dir, lenient)?;
             }
         }
-        Subcommand::New { project_name } => {
+        Subcommand::Outdated { format } => {
+            let project_root = crack::workspace_root(&project_root()?)?;
+            let outdated = crack::outdated::outdated(
+                &crack::LockFile::new(&project_root)?,
+                &crack::BuildFile::new(&project_root)?,
+                &crack::branch_commit,
+                &crack::version_tags,
+            )?;
+            match format {
+                OutdatedFormat::Table => crack::outdated::table(&outdated, &mut stdout())?,
+                OutdatedFormat::Json => crack::outdated::json(&outdated, &mut stdout())?,
+            }
+        }
+        Subcommand::New {
+            project_name,
+            project,
+            template,
+        } => {
             let project_dir = std::env::current_dir()?.join(&project_name);
-            fs::create_dir(&project_dir)?;
-            fs::write(
-                project_dir.join(crack::CFG_FILE_NAME),
-                format!("name = {project_name:?}"),
+            let name = project_dir
+                .file_name()
+                .and_then(|x| x.to_str())
+                .with_context(|| format!("{project_name:#?} has no UTF-8 name."))?;
+            if let Some(template) = template {
+                crack::project::from_template(&project_dir, name, &template)?;
+            } else {
+                fs::create_dir(&project_dir)
+                    .with_context(|| format!("Failed with {project_dir:#?} dir."))?;
+                crack::project::init(
+                    &project_dir,
+                    name,
+                    project.kind(),
+                    project.interpreter.as_deref(),
+                )?;
+            }
+            crack::project::git_init(&project_dir)?;
+        }
+        Subcommand::Init { name, project } => {
+            let project_dir = std::env::current_dir()?;
+            crack::project::init_existing(
+                &project_dir,
+                name.as_deref(),
+                project.kind(),
+                project.interpreter.as_deref(),
             )?;
+            crack::project::git_init(&project_dir)?;
         }
         Subcommand::Build {
             interpreter,
             build_file,
-        } => build_or_run(&BuildOrRun::Build, interpreter, build_file)?,
+            package,
+        } => build_or_run(
+            &BuildOrRun::Build,
+            interpreter,
+            build_file,
+            package,
+            deps_dir,
+            lenient,
+        )?,
         Subcommand::Run {
             interpreter,
             build_file,
-        } => build_or_run(&BuildOrRun::Run, interpreter, build_file)?,
-        Subcommand::Add { dep_name } => add(&dep_name, false)?,
-        Subcommand::AddDev { dev_dep_name } => add(&dev_dep_name, true)?,
+            package,
+        } => build_or_run(
+            &BuildOrRun::Run,
+            interpreter,
+            build_file,
+            package,
+            deps_dir,
+            lenient,
+        )?,
+        Subcommand::Add(args) => add(args, false, deps_dir, lenient)?,
+        Subcommand::AddDev(args) => add(args, true, deps_dir, lenient)?,
         Subcommand::UpdateRegistry => {
             let registry_dir = Path::new(&std::env::var("HOME")?).join(".crack");
             if !registry_dir.exists() {
@@ -238,9 +605,92 @@ fn main() -> Result<()> {
                 .text()?,
             )?;
         }
+        Subcommand::Tree {
+            depth,
+            invert,
+            duplicates,
+            edges,
+        } => crack::graph::tree(
+            &crack::graph::DepGraph::new(crack::BuildFile::new(&project_root()?)?)?,
+            &crack::graph::TreeOptions {
+                depth,
+                invert,
+                duplicates,
+                edges: edges.map(Into::into),
+            },
+            &mut stdout(),
+        )?,
+        Subcommand::Why { dep } => crack::graph::why(
+            &crack::graph::DepGraph::new(crack::BuildFile::new(&project_root()?)?)?,
+            &dep,
+            &mut stdout(),
+        )?,
+        Subcommand::Graph { format } => {
+            let dep_graph = crack::graph::DepGraph::new(crack::BuildFile::new(&project_root()?)?)?;
+            match format {
+                GraphFormat::Dot => crack::graph::dot(&dep_graph, &mut stdout())?,
+                GraphFormat::Json => crack::graph::json(&dep_graph, &mut stdout())?,
+            }
+        }
+        Subcommand::CheckManifest => {
+            let project_root = project_root()?;
+            let (errors, warnings) = crack::manifest::check_manifest(&project_root, lenient);
+            for problem in warnings.iter().chain(&errors) {
+                eprintln!("{problem}");
+            }
+            anyhow::ensure!(
+                errors.is_empty(),
+                "{:#?} manifest has {} errors.",
+                project_root.join(crack::CFG_FILE_NAME),
+                errors.len()
+            );
+            println!(
+                "{:#?} manifest is ok.",
+                project_root.join(crack::CFG_FILE_NAME)
+            );
+        }
         Subcommand::Completion { shell } => {
             clap_complete::generate(shell, &mut Cli::command(), "crack", &mut stdout());
         }
-    };
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_args_t_1() {
+        let add_args =
+            |args: &[&str]| match Cli::try_parse_from(["crack", "add"].iter().chain(args)) {
+                Ok(Cli {
+                    subcommand: Subcommand::Add(args),
+                    ..
+                }) => Ok((args.dep_name, args.version)),
+                Ok(_) => unreachable!(),
+                Err(err) => Err(err.kind()),
+            };
+        assert_eq!(
+            add_args(&["--version", "dep_name"]),
+            Ok((Some("dep_name".to_string()), Some("latest".to_string())))
+        );
+        assert_eq!(
+            add_args(&["dep_name", "--version=^1.2"]),
+            Ok((Some("dep_name".to_string()), Some("^1.2".to_string())))
+        );
+        assert_eq!(
+            add_args(&["dep_name", "--version", "^1.2"]),
+            Err(clap::error::ErrorKind::UnknownArgument)
+        );
+        assert_eq!(
+            add_args(&["dep_name", "--git", "https://a.git"]),
+            Err(clap::error::ErrorKind::ArgumentConflict)
+        );
+        assert_eq!(
+            add_args(&["dep_name", "--branch", "dev", "--commit", "sha"]),
+            Err(clap::error::ErrorKind::ArgumentConflict)
+        );
+        Cli::command().debug_assert();
+    }
+}
@@ -0,0 +1,841 @@
+//! Validation of ``crack.toml`` package metadata and keys and editing of ``crack.toml``.
+
+use crate::{BuildFile, Dep, DepType, CFG_FILE_NAME};
+use anyhow::{bail, ensure, Context, Result};
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+/// Ensure ``name`` starts with an ASCII letter and contains only ASCII alphanumerics,
+/// ``_`` and ``-``.
+pub fn check_name(name: &str) -> Result<()> {
+    ensure!(
+        name.starts_with(|x: char| x.is_ascii_alphabetic())
+            && name
+                .chars()
+                .all(|x| x.is_ascii_alphanumeric() || x == '_' || x == '-'),
+        "{name:?} name must start with an ASCII letter and contain only ASCII letters, digits, _ and -."
+    );
+    Ok(())
+}
+
+/// Ensure ``license`` is an SPDX expression of license ids, ``AND``, ``OR``, ``WITH``
+/// and parentheses, like ``MIT OR Apache-2.0``.
+pub fn check_license(license: &str) -> Result<()> {
+    let spaced = license.replace('(', " ( ").replace(')', " ) ");
+    let tokens: Vec<&str> = spaced.split_whitespace().collect();
+    let mut i = 0;
+    license_expr(&tokens, &mut i)?;
+    ensure!(
+        i == tokens.len(),
+        "{license:?} license has unexpected {:?}.",
+        tokens[i]
+    );
+    Ok(())
+}
+
+/// Parse ``term (AND|OR term)*`` from ``tokens[*i..]``.
+fn license_expr(tokens: &[&str], i: &mut usize) -> Result<()> {
+    license_term(tokens, i)?;
+    while let Some(&("AND" | "OR")) = tokens.get(*i) {
+        *i += 1;
+        license_term(tokens, i)?;
+    }
+    Ok(())
+}
+
+/// Parse ``(expr)`` or ``id [WITH id]`` from ``tokens[*i..]``.
+fn license_term(tokens: &[&str], i: &mut usize) -> Result<()> {
+    match tokens.get(*i) {
+        Some(&"(") => {
+            *i += 1;
+            license_expr(tokens, i)?;
+            ensure!(tokens.get(*i) == Some(&")"), "Unclosed ( in the license.");
+            *i += 1;
+        }
+        Some(id) => {
+            license_id(id)?;
+            *i += 1;
+            if tokens.get(*i) == Some(&"WITH") {
+                *i += 1;
+                license_id(tokens.get(*i).copied().unwrap_or_default())?;
+                *i += 1;
+            }
+        }
+        None => bail!("Expected a license id at the end of the license."),
+    }
+    Ok(())
+}
+
+fn license_id(id: &str) -> Result<()> {
+    let id_chars = id.strip_suffix('+').unwrap_or(id);
+    ensure!(
+        !id_chars.is_empty()
+            && !matches!(id, "AND" | "OR" | "WITH" | ")")
+            && id_chars
+                .chars()
+                .all(|x| x.is_ascii_alphanumeric() || x == '-' || x == '.'),
+        "{id:?} isn't an SPDX license id."
+    );
+    Ok(())
+}
+
+/// Ensure ``homepage`` is an absolute http or https URL.
+pub fn check_homepage(homepage: &str) -> Result<()> {
+    let url = reqwest::Url::parse(homepage)?;
+    ensure!(
+        matches!(url.scheme(), "http" | "https"),
+        "{homepage:?} homepage isn't an http or https URL."
+    );
+    Ok(())
+}
+
+/// Ensure ``keyword`` is non-empty and contains only ASCII alphanumerics, ``_``, ``-`` and ``+``.
+pub fn check_keyword(keyword: &str) -> Result<()> {
+    ensure!(
+        !keyword.is_empty()
+            && keyword
+                .chars()
+                .all(|x| x.is_ascii_alphanumeric() || matches!(x, '_' | '-' | '+')),
+        "{keyword:?} keyword must contain only ASCII letters, digits, _, - and +."
+    );
+    Ok(())
+}
+
+const CFG_KEYS: &[&str] = &[
+    "name",
+    "version",
+    "authors",
+    "license",
+    "description",
+    "homepage",
+    "keywords",
+    "interpreter",
+    "dev_deps",
+    "deps",
+    "options",
+    "groups",
+    "target",
+    "workspace",
+    "deps_dir",
+    "crack_version",
+    "interpreter_version",
+];
+const DEP_KEYS: &[&str] = &[
+    "name",
+    "repo",
+    "path",
+    "subdir",
+    "branch",
+    "commit",
+    "version",
+    "options",
+    "option_name",
+    "when",
+];
+/// Sets of dep keys, which can't be together.
+const CONFLICTING_DEP_KEYS: &[&[&str]] = &[
+    &["branch", "commit", "version"],
+    &["repo", "path"],
+    &["path", "branch"],
+    &["path", "commit"],
+    &["path", "version"],
+    &["path", "subdir"],
+];
+
+/// Return unknown keys of the ``cfg`` text with their line, column and the nearest known key.
+/// Fail on other problems of ``check_cfg``.
+pub fn check_keys(cfg: &str) -> Result<Vec<String>> {
+    let (unknown_keys, problems) = check_cfg(cfg)?;
+    ensure!(problems.is_empty(), "{}", problems.join("\n"));
+    Ok(unknown_keys)
+}
+
+/// Return unknown keys and other problems of the ``cfg`` text with their lines and columns.
+/// Problems are conflicting keys, deps without ``repo`` and ``path``, empty subdirs,
+/// subdirs outside repos and invalid targets.
+/// Fail on invalid TOML.
+pub fn check_cfg(cfg: &str) -> Result<(Vec<String>, Vec<String>)> {
+    let document = toml_edit::ImDocument::parse(cfg)?;
+    let mut checker = KeyChecker {
+        cfg,
+        unknown_keys: vec![],
+        problems: vec![],
+    };
+    let root = document.as_table();
+    checker.check(root, "the root", CFG_KEYS);
+    for (key, item) in root {
+        match key {
+            "deps" | "dev_deps" => checker.check_deps(item, key),
+            "groups" | "target" => {
+                let sub_keys: &[&str] = if key == "groups" {
+                    &["deps"]
+                } else {
+                    &["deps", "dev_deps"]
+                };
+                let Some(tables) = item.as_table_like() else {
+                    continue;
+                };
+                for (name, table) in tables.iter() {
+                    let context = format!("{key}.{name}");
+                    if key == "target" {
+                        if let Err(error) = crate::expr::Expr::parse_cfg(name) {
+                            checker.problems.push(format!(
+                                "{context} at {} isn't a valid target. {error}",
+                                checker.key_position(tables, name)
+                            ));
+                        }
+                    }
+                    if let Some(table) = table.as_table_like() {
+                        checker.check(table, &context, sub_keys);
+                        for (sub_key, deps) in table.iter() {
+                            checker.check_deps(deps, &format!("{context}.{sub_key}"));
+                        }
+                    }
+                }
+            }
+            "workspace" => {
+                if let Some(table) = item.as_table_like() {
+                    checker.check(table, key, &["members"]);
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok((checker.unknown_keys, checker.problems))
+}
+
+/// Return problems of package metadata of the ``cfg`` text with their lines and columns.
+/// Fail on invalid TOML.
+pub fn check_metadata(cfg: &str) -> Result<Vec<String>> {
+    let document = toml_edit::ImDocument::parse(cfg)?;
+    let mut checker = KeyChecker {
+        cfg,
+        unknown_keys: vec![],
+        problems: vec![],
+    };
+    let root = document.as_table();
+    for (key, item) in root {
+        let check: fn(&str) -> Result<()> = match key {
+            "name" => check_name,
+            "license" => check_license,
+            "homepage" => check_homepage,
+            "keywords" => check_keyword,
+            _ => continue,
+        };
+        let values: Vec<&str> = match item.as_array() {
+            Some(array) => array.iter().filter_map(toml_edit::Value::as_str).collect(),
+            None => item.as_str().into_iter().collect(),
+        };
+        for value in values {
+            if let Err(error) = check(value) {
+                let problem = format!(
+                    "Invalid {key:?} key of the root at {}. {error:#}",
+                    checker.key_position(root, key)
+                );
+                checker.problems.push(problem);
+            }
+        }
+    }
+    Ok(checker.problems)
+}
+
+/// Return errors and warnings of cfgs of the project in ``project_dir`` and its members
+/// with their lines and columns. Unknown keys are errors, unless ``lenient``.
+#[must_use]
+pub fn check_manifest(project_dir: &Path, lenient: bool) -> (Vec<String>, Vec<String>) {
+    let mut errors = vec![];
+    let mut warnings = vec![];
+    if let Some(cfg) = check_cfg_file(project_dir, lenient, &mut errors, &mut warnings) {
+        match cfg.workspace.member_dirs(project_dir) {
+            Ok(member_dirs) => {
+                for member_dir in member_dirs {
+                    check_cfg_file(&member_dir, lenient, &mut errors, &mut warnings);
+                }
+            }
+            Err(error) => errors.push(format!(
+                "Error in {:#?} cfg file. {error:#}",
+                project_dir.join(CFG_FILE_NAME)
+            )),
+        }
+    }
+    (errors, warnings)
+}
+
+/// Push errors and warnings of the cfg in ``dir`` to ``errors`` and ``warnings``
+/// and return the cfg, if it can be read.
+fn check_cfg_file(
+    dir: &Path,
+    lenient: bool,
+    errors: &mut Vec<String>,
+    warnings: &mut Vec<String>,
+) -> Option<crate::Cfg> {
+    let cfg_path = dir.join(CFG_FILE_NAME);
+    let error = |error: &dyn std::fmt::Display| format!("Error in {cfg_path:#?} cfg file. {error}");
+    let checked = fs::read_to_string(&cfg_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|cfg_text| Ok((check_cfg(&cfg_text)?, cfg_text)));
+    let ((unknown_keys, problems), cfg_text) = match checked {
+        Ok(checked) => checked,
+        Err(x) => {
+            errors.push(error(&x));
+            return None;
+        }
+    };
+    if lenient {
+        warnings.extend(
+            unknown_keys
+                .iter()
+                .map(|x| format!("Warning in {cfg_path:#?} cfg file. {x}")),
+        );
+    } else {
+        errors.extend(unknown_keys.iter().map(|x| error(x)));
+    }
+    errors.extend(problems.iter().map(|x| error(x)));
+    match check_metadata(&cfg_text) {
+        Ok(problems) => errors.extend(problems.iter().map(|x| error(x))),
+        Err(x) => errors.push(error(&x)),
+    }
+    toml::from_str(&cfg_text)
+        .map_err(|x| errors.push(error(&x)))
+        .ok()
+}
+
+struct KeyChecker<'a> {
+    cfg: &'a str,
+    unknown_keys: Vec<String>,
+    problems: Vec<String>,
+}
+
+impl KeyChecker<'_> {
+    /// Push keys of ``table``, which aren't in ``known_keys``, to ``unknown_keys``.
+    fn check(&mut self, table: &dyn toml_edit::TableLike, context: &str, known_keys: &[&str]) {
+        for (key, _) in table.iter() {
+            if !known_keys.contains(&key) {
+                let suggestion = nearest_key(key, known_keys)
+                    .map_or_else(String::new, |x| format!(" Did you mean {x:?}?"));
+                self.unknown_keys.push(format!(
+                    "Unknown {key:?} key of {context} at {}.{suggestion}",
+                    self.key_position(table, key)
+                ));
+            }
+        }
+    }
+
+    /// Check keys of dep tables of ``item`` and push problems of them to ``problems``.
+    fn check_deps(&mut self, item: &toml_edit::Item, context: &str) {
+        let deps: Vec<(&dyn toml_edit::TableLike, usize)> = match item {
+            toml_edit::Item::ArrayOfTables(tables) => tables
+                .iter()
+                .map(|x| {
+                    let offset = x.span().map_or(0, |x| x.start);
+                    (x as &dyn toml_edit::TableLike, offset)
+                })
+                .collect(),
+            _ => item
+                .as_array()
+                .into_iter()
+                .flat_map(|x| x.iter())
+                .filter_map(|x| x.as_inline_table())
+                .map(|x| {
+                    let offset = x.span().map_or(0, |x| x.start);
+                    (x as &dyn toml_edit::TableLike, offset)
+                })
+                .collect(),
+        };
+        for (i, (dep, offset)) in deps.into_iter().enumerate() {
+            let context = format!("{context}[{i}]");
+            self.check(dep, &context, DEP_KEYS);
+            for keys in CONFLICTING_DEP_KEYS {
+                let present: Vec<&&str> = keys.iter().filter(|x| dep.contains_key(x)).collect();
+                if present.len() > 1 {
+                    self.problems.push(format!(
+                        "{context} dep at {} has both {:?} and {:?} keys. Use one of them.",
+                        self.key_position(dep, present[1]),
+                        present[0],
+                        present[1]
+                    ));
+                }
+            }
+            if !dep.contains_key("repo") && !dep.contains_key("path") {
+                self.problems.push(format!(
+                    "{context} dep at {} has neither \"repo\" nor \"path\" key.",
+                    self.position(offset)
+                ));
+            }
+            if let Some(subdir) = dep.get("subdir").and_then(|x| x.as_str()) {
+                let components: Vec<_> = Path::new(subdir).components().collect();
+                if components.is_empty()
+                    || !components
+                        .iter()
+                        .all(|x| matches!(x, std::path::Component::Normal(_)))
+                {
+                    self.problems.push(format!(
+                        "{context} dep at {} has {subdir:?} subdir, which isn't a relative path inside the repo.",
+                        self.key_position(dep, "subdir")
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Return ``line N, column M`` of ``key`` of ``table``.
+    fn key_position(&self, table: &dyn toml_edit::TableLike, key: &str) -> String {
+        self.position(
+            table
+                .get_key_value(key)
+                .and_then(|(key, _)| key.span())
+                .map_or(0, |x| x.start),
+        )
+    }
+
+    /// Return ``line N, column M`` of ``offset`` of ``cfg``.
+    fn position(&self, offset: usize) -> String {
+        let before = &self.cfg[..offset];
+        let line =
+            before.lines().count() + usize::from(before.ends_with('\n') || before.is_empty());
+        let column = before.len() - before.rfind('\n').map_or(0, |x| x + 1) + 1;
+        format!("line {line}, column {column}")
+    }
+}
+
+/// Return the key of ``known_keys``, which is the nearest to ``key`` by the edit distance,
+/// if it's near enough.
+fn nearest_key<'a>(key: &str, known_keys: &[&'a str]) -> Option<&'a str> {
+    known_keys
+        .iter()
+        .map(|x| (edit_distance(key, x), *x))
+        .min()
+        .filter(|(distance, _)| *distance <= 2.max(key.len() / 3))
+        .map(|(_, x)| x)
+}
+
+/// Return the Levenshtein distance of ``a`` and ``b``.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let next = (row[j + 1] + 1)
+                .min(row[j] + 1)
+                .min(diagonal + usize::from(x != *y));
+            diagonal = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
+
+/// A git dep described by flags of ``crack add``.
+#[derive(Debug, Default)]
+pub struct NewDep {
+    pub repo: String,
+    pub branch: Option<String>,
+    pub commit: Option<String>,
+    /// A version requirement or ``latest`` for a caret one on the latest release tag.
+    pub version: Option<String>,
+    pub rename: Option<String>,
+    pub optional: Option<String>,
+    pub options: Vec<String>,
+}
+
+/// Return a dep of ``new_dep``. ``version_tags`` returns (version, commit)s of tags
+/// of a repo in ascending order.
+pub fn new_dep(
+    new_dep: NewDep,
+    version_tags: &impl Fn(&str) -> Result<Vec<(Version, String)>>,
+) -> Result<Dep> {
+    let repo = new_dep.repo;
+    let dep_type = match (new_dep.branch, new_dep.commit, new_dep.version) {
+        (Some(branch), _, _) => Some(DepType::Branch(branch)),
+        (None, Some(commit), _) => Some(DepType::Commit(commit)),
+        (None, None, Some(version)) if version == "latest" => {
+            let version = version_tags(&repo)?
+                .into_iter()
+                .map(|(version, _)| version)
+                .filter(|x| x.pre.is_empty())
+                .max()
+                .with_context(|| format!("There are no release tags in {repo:?} repo."))?;
+            Some(DepType::Version(VersionReq::parse(&format!("^{version}"))?))
+        }
+        (None, None, Some(version)) => Some(DepType::Version(
+            VersionReq::parse(&version)
+                .with_context(|| format!("Failed with {version:?} version."))?,
+        )),
+        (None, None, None) => None,
+    };
+    Ok(Dep {
+        name: new_dep.rename,
+        repo: Some(repo),
+        path: None,
+        subdir: None,
+        dep_type,
+        options: (!new_dep.options.is_empty()).then_some(new_dep.options),
+        option_name: new_dep.optional,
+        when: None,
+        target: None,
+    })
+}
+
+/// Add ``dep`` to ``deps`` or ``dev_deps`` of the ``CFG_FILE_NAME`` file in ``cfg_dir``
+/// keeping its comments and layout. A dep with the same name or the same source is
+/// replaced in place, if ``update``, and is an error otherwise.
+pub fn add_dep(cfg_dir: &Path, dep: &Dep, dev: bool, update: bool) -> Result<()> {
+    let cfg_path = cfg_dir.join(CFG_FILE_NAME);
+    let mut document: toml_edit::DocumentMut = fs::read_to_string(&cfg_path)
+        .with_context(|| format!("Failed with {cfg_path:#?} cfg file."))?
+        .parse()
+        .with_context(|| format!("Failed with {cfg_path:#?} cfg file."))?;
+    let key = if dev { "dev_deps" } else { "deps" };
+    let dep_table = toml::to_string(dep)?
+        .parse::<toml_edit::DocumentMut>()?
+        .as_table()
+        .clone();
+    let ensure_update = || {
+        ensure!(
+            update,
+            "{} dep is already in {key} of {cfg_path:#?} cfg file. Use --update to replace it.",
+            dep_label(dep)
+        );
+        Ok(())
+    };
+    match document
+        .entry(key)
+        .or_insert_with(|| toml_edit::Item::ArrayOfTables(toml_edit::ArrayOfTables::new()))
+    {
+        toml_edit::Item::ArrayOfTables(tables) => {
+            let same_table = tables.iter_mut().find(|x| is_same_dep(*x, dep));
+            if let Some(table) = same_table {
+                ensure_update()?;
+                table.clear();
+                for (key, item) in dep_table {
+                    table.insert(&key, item);
+                }
+            } else {
+                tables.push(dep_table);
+            }
+        }
+        toml_edit::Item::Value(toml_edit::Value::Array(array)) => {
+            let dep_value = toml_edit::Value::InlineTable(dep_table.into_inline_table());
+            let same_value = array
+                .iter_mut()
+                .find(|x| x.as_inline_table().is_some_and(|x| is_same_dep(x, dep)));
+            if let Some(value) = same_value {
+                ensure_update()?;
+                *value = dep_value;
+            } else {
+                array.push(dep_value);
+            }
+        }
+        _ => bail!("{key} of {cfg_path:#?} cfg file isn't an array."),
+    }
+    fs::write(&cfg_path, document.to_string())
+        .with_context(|| format!("Failed with {cfg_path:#?} cfg file."))
+}
+
+/// Remove deps from ``deps``, groups and targets or from ``dev_deps`` and targets of
+/// the ``CFG_FILE_NAME`` file in ``cfg_dir`` keeping its comments and layout.
+/// Deps are removed by ``name``s, repos, paths, repos of ``names`` in ``registry`` or
+/// names of their packages. Package names of git deps are taken from
+/// the ``BUILD_FILE_NAME`` file in ``cfg_dir``, if there is one.
+/// Fail on names, which match no dep.
+pub fn remove_deps(
+    cfg_dir: &Path,
+    names: &[String],
+    registry: &HashMap<String, String>,
+    dev: bool,
+) -> Result<()> {
+    let cfg_path = cfg_dir.join(CFG_FILE_NAME);
+    let mut document: toml_edit::DocumentMut = fs::read_to_string(&cfg_path)
+        .with_context(|| format!("Failed with {cfg_path:#?} cfg file."))?
+        .parse()
+        .with_context(|| format!("Failed with {cfg_path:#?} cfg file."))?;
+    let build_file = BuildFile::new(cfg_dir).ok();
+    let key = if dev { "dev_deps" } else { "deps" };
+    let mut removed_names = HashSet::new();
+    let mut is_kept = |table: &dyn toml_edit::TableLike| {
+        let package_name = package_name(cfg_dir, table, build_file.as_ref());
+        let name = names.iter().find(|x| {
+            is_named(table, x, registry)
+                || ["repo", "path"]
+                    .into_iter()
+                    .any(|key| table.get(key).and_then(toml_edit::Item::as_str) == Some(x))
+                || (!table.contains_key("name") && package_name.as_deref() == Some(x))
+        });
+        if let Some(name) = name {
+            removed_names.insert(name.clone());
+        }
+        name.is_none()
+    };
+    for (context, item) in dep_arrays_mut(&mut document) {
+        if context.rsplit('.').next() != Some(key) {
+            continue;
+        }
+        match item {
+            toml_edit::Item::ArrayOfTables(tables) => tables.retain(|x| is_kept(x)),
+            toml_edit::Item::Value(toml_edit::Value::Array(array)) => {
+                let first_prefix = array.get(0).and_then(|x| x.decor().prefix().cloned());
+                array.retain(|x| x.as_inline_table().is_none_or(|x| is_kept(x)));
+                if let (Some(first), Some(prefix)) = (array.get_mut(0), first_prefix) {
+                    first.decor_mut().set_prefix(prefix);
+                }
+            }
+            _ => bail!("{context} of {cfg_path:#?} cfg file isn't an array."),
+        }
+    }
+    for name in names {
+        ensure!(
+            removed_names.contains(name),
+            "There is no {name:?} dep in {key} of {cfg_path:#?} cfg file."
+        );
+    }
+    fs::write(&cfg_path, document.to_string())
+        .with_context(|| format!("Failed with {cfg_path:#?} cfg file."))
+}
+
+/// Return the package name of the dep of ``table`` in the ``cfg_dir`` cfg.
+/// A path dep name is read from its cfg, a git dep name is taken from ``build_file``.
+fn package_name(
+    cfg_dir: &Path,
+    table: &dyn toml_edit::TableLike,
+    build_file: Option<&BuildFile>,
+) -> Option<String> {
+    #[derive(Deserialize)]
+    struct Named {
+        name: String,
+    }
+
+    let get = |key| table.get(key).and_then(toml_edit::Item::as_str);
+    if let Some(path) = get("path") {
+        let cfg = fs::read_to_string(cfg_dir.join(path).join(CFG_FILE_NAME)).ok()?;
+        return toml::from_str::<Named>(&cfg).ok().map(|x| x.name);
+    }
+    let (repo, subdir) = (get("repo")?, get("subdir"));
+    let units: Vec<_> = build_file?.sccs.iter().flatten().collect();
+    let root = units.iter().find(|x| x.root)?;
+    root.name_map.values().find_map(|dir| {
+        let unit = units.iter().find(|x| &x.dir == dir)?;
+        let dir = Path::new(&unit.dir);
+        let is_subdir_matched = match subdir {
+            Some(subdir) => dir.components().count() > 1 && dir.ends_with(subdir),
+            None => dir.components().count() == 1,
+        };
+        (unit.lock.as_ref()?.repo == repo && is_subdir_matched).then(|| unit.name.clone())
+    })
+}
+
+/// A version requirement change of a dep.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Upgrade {
+    /// A name or a repo of the dep.
+    pub dep: String,
+    pub from: VersionReq,
+    pub to: VersionReq,
+}
+
+/// Set version requirements of version deps of the ``CFG_FILE_NAME`` file in ``cfg_dir``
+/// to the newest release of ``version_tags`` of their repos keeping comments and layout.
+/// If ``compatible``, the release must match the current requirement.
+/// Single comparator requirements keep their operator, others become caret ones.
+/// Only deps of ``names`` are upgraded, if there are ``names``.
+/// They are names or registry names like in ``remove_deps``.
+/// The file isn't written, if ``dry_run``.
+pub fn upgrade_deps(
+    cfg_dir: &Path,
+    names: &[String],
+    registry: &HashMap<String, String>,
+    compatible: bool,
+    dry_run: bool,
+    version_tags: &impl Fn(&str) -> Result<Vec<(Version, String)>>,
+) -> Result<Vec<Upgrade>> {
+    let cfg_path = cfg_dir.join(CFG_FILE_NAME);
+    let mut document: toml_edit::DocumentMut = fs::read_to_string(&cfg_path)
+        .with_context(|| format!("Failed with {cfg_path:#?} cfg file."))?
+        .parse()
+        .with_context(|| format!("Failed with {cfg_path:#?} cfg file."))?;
+    let mut upgrades = vec![];
+    let mut upgraded_names = HashSet::new();
+    let mut repo_versions: HashMap<String, Vec<Version>> = HashMap::new();
+    for table in dep_tables_mut(&mut document) {
+        if !names.is_empty() {
+            let Some(name) = names.iter().find(|x| is_named(table, x, registry)) else {
+                continue;
+            };
+            upgraded_names.insert(name.clone());
+        }
+        let get = |key| table.get(key).and_then(toml_edit::Item::as_str);
+        let (Some(repo), Some(from)) = (get("repo"), get("version")) else {
+            continue;
+        };
+        let from = VersionReq::parse(from)
+            .with_context(|| format!("Failed with {from:?} version of {repo:?} dep."))?;
+        if !repo_versions.contains_key(repo) {
+            let versions = version_tags(repo)?
+                .into_iter()
+                .map(|(version, _)| version)
+                .filter(|x| x.pre.is_empty())
+                .collect();
+            repo_versions.insert(repo.to_string(), versions);
+        }
+        let Some(newest) = repo_versions[repo]
+            .iter()
+            .filter(|x| !compatible || from.matches(x))
+            .max()
+        else {
+            continue;
+        };
+        // Other operators don't keep their meaning with the newest version.
+        let kept = match from.comparators.as_slice() {
+            [comparator]
+                if matches!(
+                    comparator.op,
+                    semver::Op::Caret
+                        | semver::Op::Tilde
+                        | semver::Op::GreaterEq
+                        | semver::Op::Exact
+                ) =>
+            {
+                Some(comparator)
+            }
+            _ => None,
+        };
+        // The newest version is already the lower bound of ``from``, like 1.2.0 of ``1.2``.
+        if kept.is_some_and(|x| {
+            x.pre.is_empty()
+                && *newest == Version::new(x.major, x.minor.unwrap_or(0), x.patch.unwrap_or(0))
+        }) {
+            continue;
+        }
+        let op = kept.map_or(semver::Op::Caret, |x| x.op);
+        let to = VersionReq {
+            comparators: vec![semver::Comparator {
+                op,
+                major: newest.major,
+                minor: Some(newest.minor),
+                patch: Some(newest.patch),
+                pre: semver::Prerelease::EMPTY,
+            }],
+        };
+        if to != from {
+            upgrades.push(Upgrade {
+                dep: get("name").unwrap_or(repo).to_string(),
+                from,
+                to: to.clone(),
+            });
+            let value = table.get_mut("version").unwrap().as_value_mut().unwrap();
+            let decor = value.decor().clone();
+            *value = toml_edit::Value::from(to.to_string());
+            *value.decor_mut() = decor;
+        }
+    }
+    for name in names {
+        ensure!(
+            upgraded_names.contains(name),
+            "There is no {name:?} dep in {cfg_path:#?} cfg file."
+        );
+    }
+    if !dry_run {
+        fs::write(&cfg_path, document.to_string())
+            .with_context(|| format!("Failed with {cfg_path:#?} cfg file."))?;
+    }
+    Ok(upgrades)
+}
+
+/// Return dep tables of ``deps``, ``dev_deps``, groups and targets of ``document``.
+fn dep_tables_mut(document: &mut toml_edit::DocumentMut) -> Vec<&mut dyn toml_edit::TableLike> {
+    dep_arrays_mut(document)
+        .into_iter()
+        .flat_map(|(_, item)| array_tables_mut(item))
+        .collect()
+}
+
+/// Return (context, item)s of ``deps``, ``dev_deps``, groups and targets dep arrays of
+/// ``document``. A context is a dotted key path, which ends with ``deps`` or ``dev_deps``.
+fn dep_arrays_mut(document: &mut toml_edit::DocumentMut) -> Vec<(String, &mut toml_edit::Item)> {
+    let mut arrays = vec![];
+    for (key, item) in document.iter_mut() {
+        match key.get() {
+            "deps" | "dev_deps" => arrays.push((key.get().to_string(), item)),
+            "groups" | "target" => {
+                let context = key.get().to_string();
+                for (name, item) in item
+                    .as_table_like_mut()
+                    .into_iter()
+                    .flat_map(toml_edit::TableLike::iter_mut)
+                {
+                    let context = format!("{context}.{}", name.get());
+                    for (key, item) in item
+                        .as_table_like_mut()
+                        .into_iter()
+                        .flat_map(toml_edit::TableLike::iter_mut)
+                    {
+                        if matches!(key.get(), "deps" | "dev_deps") {
+                            arrays.push((format!("{context}.{}", key.get()), item));
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    arrays
+}
+
+/// Return tables of an array of tables or inline tables of an array.
+fn array_tables_mut(item: &mut toml_edit::Item) -> Vec<&mut dyn toml_edit::TableLike> {
+    match item {
+        toml_edit::Item::ArrayOfTables(tables) => tables
+            .iter_mut()
+            .map(|x| x as &mut dyn toml_edit::TableLike)
+            .collect(),
+        _ => item
+            .as_array_mut()
+            .into_iter()
+            .flat_map(toml_edit::Array::iter_mut)
+            .filter_map(toml_edit::Value::as_inline_table_mut)
+            .map(|x| x as &mut dyn toml_edit::TableLike)
+            .collect(),
+    }
+}
+
+/// Return whether ``table`` has the ``name`` key of ``name`` or the repo of ``name``
+/// in ``registry``.
+fn is_named(
+    table: &dyn toml_edit::TableLike,
+    name: &str,
+    registry: &HashMap<String, String>,
+) -> bool {
+    let get = |key| table.get(key).and_then(toml_edit::Item::as_str);
+    get("name") == Some(name)
+        || get("repo").is_some_and(|repo| registry.get(name).is_some_and(|x| x == repo))
+}
+
+/// Return whether ``table`` has the ``name`` of ``dep`` or, if they both have no names,
+/// the same ``repo``, ``path`` and ``subdir``.
+fn is_same_dep(table: &dyn toml_edit::TableLike, dep: &Dep) -> bool {
+    let get = |key| table.get(key).and_then(toml_edit::Item::as_str);
+    match &dep.name {
+        Some(name) => get("name") == Some(name),
+        None => {
+            get("name").is_none()
+                && get("repo") == dep.repo.as_deref()
+                && get("path") == dep.path.as_deref().and_then(Path::to_str)
+                && get("subdir").map(Path::new) == dep.subdir.as_deref()
+        }
+    }
+}
+
+/// Return the name, the repo or the path of ``dep``.
+fn dep_label(dep: &Dep) -> String {
+    dep.name
+        .clone()
+        .or_else(|| dep.repo.clone())
+        .or_else(|| dep.path.as_ref().map(|x| x.display().to_string()))
+        .unwrap_or_default()
+}
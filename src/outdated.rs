@@ -0,0 +1,141 @@
+//! A report of locked deps, which have newer commits or versions.
+
+use crate::{BuildFile, LockFile, LockType, LockUnit};
+use anyhow::Result;
+use semver::{Version, VersionReq};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+
+/// A locked dep with its newest commits and versions.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Outdated {
+    pub lock: LockUnit,
+    /// A version of the tag, which the lock commit was selected by.
+    pub version: Option<Version>,
+    /// Requirements, which the lock commit was selected by.
+    pub reqs: Vec<VersionReq>,
+    /// The newest commit of the lock branch.
+    pub branch_commit: Option<String>,
+    /// The newest release tag, which matches ``reqs``.
+    pub compatible_version: Option<Version>,
+    /// The newest release tag.
+    pub latest_version: Option<Version>,
+}
+
+/// Return ``Outdated``s of locks of ``lock_file``.
+/// Versions and requirements are from ``build_file``.
+/// ``branch_commit`` is queried for branch locks and ``version_tags`` for version ones.
+pub fn outdated(
+    lock_file: &LockFile,
+    build_file: &BuildFile,
+    branch_commit: &impl Fn(&str, &str) -> Result<String>,
+    version_tags: &impl Fn(&str) -> Result<Vec<(Version, String)>>,
+) -> Result<Vec<Outdated>> {
+    let locks: BTreeSet<&LockUnit> = lock_file
+        .locks
+        .iter()
+        .chain(&lock_file.dev_locks)
+        .chain(lock_file.group_locks.values().flatten())
+        .chain(lock_file.target_locks.values().flatten())
+        .collect();
+    let units: Vec<_> = build_file.sccs.iter().flatten().collect();
+    let dir_locks: HashMap<_, _> = units
+        .iter()
+        .filter_map(|x| Some((&x.dir, x.lock.as_ref()?)))
+        .collect();
+    let mut lock_reqs: HashMap<&LockUnit, Vec<VersionReq>> = HashMap::new();
+    for unit in &units {
+        for (name, dep_req) in &unit.dep_reqs {
+            let lock = unit.name_map.get(name).and_then(|x| dir_locks.get(x));
+            if let (Some(lock), Some(req)) = (lock, &dep_req.version) {
+                let reqs = lock_reqs.entry(lock).or_default();
+                if !reqs.contains(req) {
+                    reqs.push(req.clone());
+                }
+            }
+        }
+    }
+    let mut outdated = vec![];
+    for lock in locks {
+        let version = units
+            .iter()
+            .filter(|x| x.lock.as_ref() == Some(lock))
+            .find_map(|x| x.version.clone());
+        let reqs = lock_reqs.remove(lock).unwrap_or_default();
+        let branch_commit = match &lock.lock_type {
+            LockType::Branch(branch) => Some(branch_commit(&lock.repo, branch)?),
+            LockType::Commit(_) => None,
+        };
+        let (mut compatible_version, mut latest_version) = (None, None);
+        if version.is_some() || !reqs.is_empty() {
+            let versions: Vec<Version> = version_tags(&lock.repo)?
+                .into_iter()
+                .map(|(version, _)| version)
+                .filter(|x| x.pre.is_empty())
+                .collect();
+            compatible_version = versions
+                .iter()
+                .filter(|x| reqs.iter().all(|req| req.matches(x)))
+                .max()
+                .cloned();
+            latest_version = versions.into_iter().max();
+        }
+        outdated.push(Outdated {
+            lock: lock.clone(),
+            version,
+            reqs,
+            branch_commit,
+            compatible_version,
+            latest_version,
+        });
+    }
+    Ok(outdated)
+}
+
+/// Write ``outdated`` as a table with a header.
+pub fn table(outdated: &[Outdated], buffer: &mut impl std::io::Write) -> Result<()> {
+    let option = |x: Option<String>| x.unwrap_or_else(|| "-".to_string());
+    let mut rows = vec![[
+        "repo".to_string(),
+        "lock".to_string(),
+        "version".to_string(),
+        "branch commit".to_string(),
+        "compatible".to_string(),
+        "latest".to_string(),
+    ]];
+    for x in outdated {
+        rows.push([
+            x.lock.repo.clone(),
+            match &x.lock.lock_type {
+                LockType::Branch(branch) => format!("branch {branch}"),
+                LockType::Commit(commit) => format!("commit {commit}"),
+            },
+            option(x.version.as_ref().map(ToString::to_string)),
+            option(x.branch_commit.clone()),
+            option(x.compatible_version.as_ref().map(ToString::to_string)),
+            option(x.latest_version.as_ref().map(ToString::to_string)),
+        ]);
+    }
+    let mut widths = [0; 6];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect();
+        writeln!(buffer, "{}", cells.join("  ").trim_end())?;
+    }
+    Ok(())
+}
+
+/// Write ``outdated`` as a JSON array.
+pub fn json(outdated: &[Outdated], buffer: &mut impl std::io::Write) -> Result<()> {
+    serde_json::to_writer_pretty(&mut *buffer, outdated)?;
+    writeln!(buffer)?;
+    Ok(())
+}
@@ -0,0 +1,155 @@
+//! Creation of project files for ``crack new`` and ``crack init``.
+
+use crate::{manifest, with_stderr, CFG_FILE_NAME};
+use anyhow::{bail, ensure, Context, Result};
+use std::{fs, path::Path, process::Command};
+
+/// A kind of a project source skeleton.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectKind {
+    Bin,
+    Lib,
+}
+
+/// Create the ``CFG_FILE_NAME`` file, a source skeleton of ``kind`` and ``.gitignore``,
+/// which excludes the deps dir, in ``dir``. Existing files are left alone.
+pub fn init(dir: &Path, name: &str, kind: ProjectKind, interpreter: Option<&Path>) -> Result<()> {
+    manifest::check_name(name)?;
+    let interpreter = match interpreter {
+        Some(interpreter) => {
+            ensure!(
+                interpreter.is_absolute(),
+                "The interpreter path must be absolute."
+            );
+            let interpreter = interpreter
+                .to_str()
+                .with_context(|| format!("{interpreter:#?} isn't UTF-8."))?;
+            format!("interpreter = {}\n", toml::Value::from(interpreter))
+        }
+        None => String::new(),
+    };
+    let cfg = format!("name = {}\n{interpreter}", toml::Value::from(name));
+    let src_file = match kind {
+        ProjectKind::Bin => "main.sk",
+        ProjectKind::Lib => "lib.sk",
+    };
+    fs::create_dir_all(dir.join("src"))?;
+    for (path, content) in [
+        (Path::new(CFG_FILE_NAME), cfg.as_str()),
+        (&Path::new("src").join(src_file), ""),
+        (Path::new(".gitignore"), "/deps\n"),
+    ] {
+        let path = dir.join(path);
+        if !path.exists() {
+            fs::write(&path, content).with_context(|| format!("Failed with {path:#?} file."))?;
+        }
+    }
+    Ok(())
+}
+
+/// ``init`` an existing ``dir``. The project name is ``name`` or the dir name.
+/// Fail, if there is the ``CFG_FILE_NAME`` file in ``dir`` or its ancestors.
+pub fn init_existing(
+    dir: &Path,
+    name: Option<&str>,
+    kind: ProjectKind,
+    interpreter: Option<&Path>,
+) -> Result<()> {
+    if let Some(project_root) = dir.ancestors().find(|x| x.join(CFG_FILE_NAME).exists()) {
+        bail!("{:#?} already exists.", project_root.join(CFG_FILE_NAME));
+    }
+    let name = match name {
+        Some(name) => name,
+        None => dir
+            .file_name()
+            .and_then(|x| x.to_str())
+            .with_context(|| format!("{dir:#?} has no UTF-8 name. Use --name."))?,
+    };
+    manifest::check_name(name).context("Use --name to set another name.")?;
+    init(dir, name, kind, interpreter)
+}
+
+/// Copy the ``template`` dir or clone the ``template`` repo to ``dir``, which mustn't exist,
+/// and substitute ``{{name}}`` with ``name`` in file names and UTF-8 file contents.
+/// ``.git`` of the template is skipped.
+pub fn from_template(dir: &Path, name: &str, template: &str) -> Result<()> {
+    manifest::check_name(name)?;
+    ensure!(!dir.exists(), "{dir:#?} already exists.");
+    if Path::new(template).is_dir() {
+        copy_dir(Path::new(template), dir)?;
+    } else {
+        with_stderr(
+            &Command::new("git")
+                .arg("clone")
+                .arg("-q")
+                .arg("--depth=1")
+                .arg(template)
+                .arg(dir)
+                .output()
+                .with_context(|| format!("Failed with {template:?} template."))?,
+        )
+        .with_context(|| format!("Failed with {template:?} template."))?;
+        fs::remove_dir_all(dir.join(".git"))?;
+    }
+    substitute_name(dir, name)
+}
+
+/// Copy ``from`` dir to ``to`` dir except ``.git``.
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir(to).with_context(|| format!("Failed with {to:#?} dir."))?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &to.join(entry.file_name()))?;
+        } else {
+            fs::copy(entry.path(), to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Substitute ``{{name}}`` with ``name`` in file names and UTF-8 file contents in ``dir``.
+fn substitute_name(dir: &Path, name: &str) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let mut path = entry.path();
+        let file_name = entry
+            .file_name()
+            .to_string_lossy()
+            .replace("{{name}}", name);
+        if file_name != entry.file_name().to_string_lossy() {
+            path = dir.join(file_name);
+            fs::rename(entry.path(), &path)?;
+        }
+        if entry.file_type()?.is_dir() {
+            substitute_name(&path, name)?;
+        } else if let Ok(content) = fs::read_to_string(&path) {
+            fs::write(&path, content.replace("{{name}}", name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Run ``git init`` in ``dir``, unless it's inside a git work tree already.
+pub fn git_init(dir: &Path) -> Result<()> {
+    let is_work_tree = Command::new("git")
+        .current_dir(dir)
+        .arg("rev-parse")
+        .arg("--is-inside-work-tree")
+        .output()
+        .is_ok_and(|x| x.status.success());
+    if !is_work_tree {
+        with_stderr(
+            &Command::new("git")
+                .current_dir(dir)
+                .arg("init")
+                .arg("-q")
+                .output()
+                .with_context(|| format!("Failed with {dir:#?} dir."))?,
+        )?;
+    }
+    Ok(())
+}
@@ -79,6 +79,7 @@ fn version_tags_t_1() {
     );
 }
 
+#[allow(clippy::too_many_lines)]
 fn stub_installer(_deps_dir: &Path, dep_dir_path: &Path, lock: &LockUnit) -> Result<()> {
     if !Path::new(dep_dir_path).exists() {
         std::fs::create_dir(dep_dir_path)?;
@@ -130,6 +131,49 @@ fn stub_installer(_deps_dir: &Path, dep_dir_path: &Path, lock: &LockUnit) -> Res
                         repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
                     "#
                     }
+                    "with_options" => {
+                        r#"
+                        name = "otherFiles"
+
+                        [options]
+                        default = ["json"]
+                        full = ["net", "json"]
+                        json = []
+
+                        [[deps]]
+                        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+                        option_name = "net"
+                        "#
+                    }
+                    "enables_net" => {
+                        r#"
+                        name = "enablesNet"
+
+                        [[deps]]
+                        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+                        branch = "with_options"
+                        options = ["net"]
+                        "#
+                    }
+                    "monorepo" => {
+                        for (package, dep_branch) in [("core", "default"), ("http", "main")] {
+                            let package_dir = dep_dir_path.join("packages").join(package);
+                            fs::create_dir_all(&package_dir)?;
+                            fs::write(
+                                package_dir.join(CFG_FILE_NAME),
+                                format!(
+                                    r#"
+                                    name = "{package}"
+
+                                    [[deps]]
+                                    repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+                                    branch = "{dep_branch}"
+                                    "#
+                                ),
+                            )?;
+                        }
+                        r#"name = "monorepo""#
+                    }
                     "optional_branch" => {
                         r#"
                         name = "otherFiles"
@@ -156,7 +200,7 @@ fn stub_installer(_deps_dir: &Path, dep_dir_path: &Path, lock: &LockUnit) -> Res
             },
         )
         .unwrap();
-    };
+    }
     Ok(())
 }
 
@@ -176,10 +220,11 @@ fn nfiles(dir: &Path) -> usize {
 }
 
 fn build_file(dir: &Path) -> Vec<Vec<BuildUnit>> {
-    serde_json::from_str(&fs::read_to_string(dir.join("crack.build")).unwrap()).unwrap()
+    BuildFile::new(dir).unwrap().sccs
 }
 
 #[test]
+#[allow(clippy::too_many_lines)]
 fn install_t_1() {
     let tmp_dir = tempfile::tempdir().unwrap();
     fs::write(
@@ -193,7 +238,17 @@ fn install_t_1() {
     )
     .unwrap();
     let deps_dir = tmp_dir.path().join("deps");
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
     assert_unord_eq(
         &LockFile::new(tmp_dir.path()).unwrap().locks,
         &[LockUnit {
@@ -206,14 +261,41 @@ fn install_t_1() {
         vec![
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.default"),
-                name_map: BTreeMap::new()
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.default"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("default".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
+                name_map: BTreeMap::new(),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
-                dir: OsString::from("root"),
+                dir: tmp_dir.path().as_os_str().to_os_string(),
+                name: "package_name".to_string(),
+                path: PathBuf::from("."),
+                root: true,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: None,
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "otherFiles".to_string(),
                     OsString::from("WinstonMDP.githubOtherFiles.branch.default")
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }]
         ]
     );
@@ -221,7 +303,17 @@ fn install_t_1() {
         &deps_dir.join("WinstonMDP.githubOtherFiles.branch.default")
     ));
     assert_eq!(nfiles(&deps_dir), 1);
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
     assert_unord_eq(
         &LockFile::new(tmp_dir.path()).unwrap().locks,
         &[LockUnit {
@@ -234,14 +326,41 @@ fn install_t_1() {
         vec![
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.default"),
-                name_map: BTreeMap::new()
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.default"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("default".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
+                name_map: BTreeMap::new(),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
-                dir: OsString::from("root"),
+                dir: tmp_dir.path().as_os_str().to_os_string(),
+                name: "package_name".to_string(),
+                path: PathBuf::from("."),
+                root: true,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: None,
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "otherFiles".to_string(),
                     OsString::from("WinstonMDP.githubOtherFiles.branch.default")
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }]
         ]
     );
@@ -267,7 +386,17 @@ fn install_t_2() {
     .unwrap();
     let deps_dir = tmp_dir.path().join("deps");
     fs::create_dir(&deps_dir).unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
     assert_unord_eq(
         &LockFile::new(tmp_dir.path()).unwrap().locks,
         &[LockUnit {
@@ -280,14 +409,41 @@ fn install_t_2() {
         vec![
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.main"),
-                name_map: BTreeMap::new()
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.main"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("main".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
+                name_map: BTreeMap::new(),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
-                dir: OsString::from("root"),
+                dir: tmp_dir.path().as_os_str().to_os_string(),
+                name: "package_name".to_string(),
+                path: PathBuf::from("."),
+                root: true,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: None,
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "otherFiles".to_string(),
                     OsString::from("WinstonMDP.githubOtherFiles.branch.main")
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }]
         ]
     );
@@ -298,6 +454,7 @@ fn install_t_2() {
 }
 
 #[test]
+#[allow(clippy::too_many_lines)]
 fn install_t_3() {
     let tmp_dir = tempfile::tempdir().unwrap();
     fs::write(
@@ -316,7 +473,17 @@ fn install_t_3() {
     )
     .unwrap();
     let deps_dir = tmp_dir.path().join("deps");
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
     assert_unord_eq(
         &LockFile::new(tmp_dir.path()).unwrap().locks,
         &[
@@ -335,14 +502,53 @@ fn install_t_3() {
         vec![
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.b"),
-                name_map: BTreeMap::new()
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.b"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("b".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
+                name_map: BTreeMap::new(),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.default"),
-                name_map: BTreeMap::new()
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.default"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("default".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
+                name_map: BTreeMap::new(),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
-                dir: OsString::from("root"),
+                dir: tmp_dir.path().as_os_str().to_os_string(),
+                name: "package_name".to_string(),
+                path: PathBuf::from("."),
+                root: true,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: None,
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([
                     (
                         "otherFiles".to_string(),
@@ -352,7 +558,10 @@ fn install_t_3() {
                         "name_for_b".to_string(),
                         OsString::from("WinstonMDP.githubOtherFiles.branch.b")
                     )
-                ])
+                ]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }]
         ]
     );
@@ -382,7 +591,17 @@ fn install_t_4() {
     .unwrap();
     let deps_dir = tmp_dir.path().join("deps");
     fs::create_dir(&deps_dir).unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
     assert_unord_eq(
         &LockFile::new(tmp_dir.path()).unwrap().locks,
         &[
@@ -401,21 +620,63 @@ fn install_t_4() {
         vec![
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.default"),
-                name_map: BTreeMap::new()
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.default"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("default".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
+                name_map: BTreeMap::new(),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.with_dependencies"),
+                name: "otherDependencies".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.with_dependencies"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("with_dependencies".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "otherFiles".to_string(),
                     OsString::from("WinstonMDP.githubOtherFiles.branch.default")
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
-                dir: OsString::from("root"),
+                dir: tmp_dir.path().as_os_str().to_os_string(),
+                name: "package_name".to_string(),
+                path: PathBuf::from("."),
+                root: true,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: None,
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "otherDependencies".to_string(),
                     OsString::from("WinstonMDP.githubOtherFiles.branch.with_dependencies")
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }]
         ]
     );
@@ -437,7 +698,17 @@ fn install_t_4() {
         "#,
     )
     .unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
     assert_unord_eq(
         &LockFile::new(tmp_dir.path()).unwrap().locks,
         &[
@@ -456,21 +727,63 @@ fn install_t_4() {
         vec![
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.default"),
-                name_map: BTreeMap::new()
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.default"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("default".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
+                name_map: BTreeMap::new(),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.with_dependencies"),
+                name: "otherDependencies".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.with_dependencies"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("with_dependencies".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "otherFiles".to_string(),
                     OsString::from("WinstonMDP.githubOtherFiles.branch.default")
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
-                dir: OsString::from("root"),
+                dir: tmp_dir.path().as_os_str().to_os_string(),
+                name: "package_name".to_string(),
+                path: PathBuf::from("."),
+                root: true,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: None,
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "otherDependencies".to_string(),
                     OsString::from("WinstonMDP.githubOtherFiles.branch.with_dependencies")
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }]
         ]
     );
@@ -499,7 +812,17 @@ fn install_t_5() {
     .unwrap();
     let deps_dir = tmp_dir.path().join("deps");
     fs::create_dir(&deps_dir).unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
     assert_unord_eq(
         &LockFile::new(tmp_dir.path()).unwrap().locks,
         &[{
@@ -516,16 +839,43 @@ fn install_t_5() {
                 dir: OsString::from(
                     "WinstonMDP.githubOtherFiles.commit.909896f5646b7fd9f058dcd21961b8d5599dec3b"
                 ),
-                name_map: BTreeMap::new()
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.commit.909896f5646b7fd9f058dcd21961b8d5599dec3b"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Commit("909896f5646b7fd9f058dcd21961b8d5599dec3b".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
+                name_map: BTreeMap::new(),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
-                dir: OsString::from("root"),
+                dir: tmp_dir.path().as_os_str().to_os_string(),
+                name: "package_name".to_string(),
+                path: PathBuf::from("."),
+                root: true,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: None,
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "otherFiles".to_string(),
                     OsString::from(
                         "WinstonMDP.githubOtherFiles.commit.909896f5646b7fd9f058dcd21961b8d5599dec3b"
                     )
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }]
         ]
     );
@@ -536,6 +886,7 @@ fn install_t_5() {
 }
 
 #[test]
+#[allow(clippy::too_many_lines)]
 fn install_t_6() {
     let tmp_dir = tempfile::tempdir().unwrap();
     fs::write(
@@ -552,7 +903,17 @@ fn install_t_6() {
     .unwrap();
     let deps_dir = tmp_dir.path().join("deps");
     fs::create_dir(&deps_dir).unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
     assert_unord_eq(
         &LockFile::new(tmp_dir.path()).unwrap().locks,
         &[
@@ -571,25 +932,67 @@ fn install_t_6() {
         vec![
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.default"),
-                name_map: BTreeMap::new()
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.default"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("default".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
+                name_map: BTreeMap::new(),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
                 dir: OsString::from(
                     "WinstonMDP.githubOtherFiles.commit.30cfb86f4e76810eedc1d8d57167289a2b63b4ac"
                 ),
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.commit.30cfb86f4e76810eedc1d8d57167289a2b63b4ac"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Commit("30cfb86f4e76810eedc1d8d57167289a2b63b4ac".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "otherFiles".to_string(),
                     OsString::from("WinstonMDP.githubOtherFiles.branch.default")
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
-                dir: OsString::from("root"),
+                dir: tmp_dir.path().as_os_str().to_os_string(),
+                name: "package_name".to_string(),
+                path: PathBuf::from("."),
+                root: true,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: None,
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "commit_package".to_string(),
                     OsString::from(
                         "WinstonMDP.githubOtherFiles.commit.30cfb86f4e76810eedc1d8d57167289a2b63b4ac"
                     )
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }]
         ]
     );
@@ -603,6 +1006,7 @@ fn install_t_6() {
 }
 
 #[test]
+#[allow(clippy::too_many_lines)]
 fn install_t_7() {
     let tmp_dir = tempfile::tempdir().unwrap();
     fs::write(
@@ -617,7 +1021,17 @@ fn install_t_7() {
     .unwrap();
     let deps_dir = tmp_dir.path().join("deps");
     fs::create_dir(&deps_dir).unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
     assert_unord_eq(
         &LockFile::new(tmp_dir.path()).unwrap().locks,
         &[LockUnit {
@@ -630,14 +1044,41 @@ fn install_t_7() {
         vec![
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.default"),
-                name_map: BTreeMap::new()
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.default"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("default".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
+                name_map: BTreeMap::new(),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
-                dir: OsString::from("root"),
+                dir: tmp_dir.path().as_os_str().to_os_string(),
+                name: "package_name".to_string(),
+                path: PathBuf::from("."),
+                root: true,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: None,
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "otherFiles".to_string(),
                     OsString::from("WinstonMDP.githubOtherFiles.branch.default")
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }]
         ]
     );
@@ -659,7 +1100,17 @@ fn install_t_7() {
         "#,
     )
     .unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
     assert_unord_eq(
         &LockFile::new(tmp_dir.path()).unwrap().locks,
         &[LockUnit {
@@ -672,14 +1123,41 @@ fn install_t_7() {
         vec![
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.b"),
-                name_map: BTreeMap::new()
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.b"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("b".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
+                name_map: BTreeMap::new(),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
-                dir: OsString::from("root"),
+                dir: tmp_dir.path().as_os_str().to_os_string(),
+                name: "package_name".to_string(),
+                path: PathBuf::from("."),
+                root: true,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: None,
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "otherFiles".to_string(),
                     OsString::from("WinstonMDP.githubOtherFiles.branch.b")
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }]
         ]
     );
@@ -708,7 +1186,17 @@ fn install_t_8() {
     .unwrap();
     let deps_dir = tmp_dir.path().join("deps");
     fs::create_dir(&deps_dir).unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
     assert_unord_eq(
         &LockFile::new(tmp_dir.path()).unwrap().locks,
         &[LockUnit {
@@ -721,14 +1209,41 @@ fn install_t_8() {
         vec![
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.default"),
-                name_map: BTreeMap::new()
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.default"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("default".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
+                name_map: BTreeMap::new(),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
-                dir: OsString::from("root"),
+                dir: tmp_dir.path().as_os_str().to_os_string(),
+                name: "package_name".to_string(),
+                path: PathBuf::from("."),
+                root: true,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: None,
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "otherFiles".to_string(),
                     OsString::from("WinstonMDP.githubOtherFiles.branch.default")
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }]
         ]
     );
@@ -747,7 +1262,17 @@ fn install_t_8() {
         "#,
     )
     .unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
     assert_unord_eq(
         &LockFile::new(tmp_dir.path()).unwrap().locks,
         &[
@@ -766,21 +1291,63 @@ fn install_t_8() {
         vec![
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.default"),
-                name_map: BTreeMap::new()
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.default"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("default".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
+                name_map: BTreeMap::new(),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.with_dependencies"),
+                name: "otherDependencies".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.with_dependencies"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("with_dependencies".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "otherFiles".to_string(),
                     OsString::from("WinstonMDP.githubOtherFiles.branch.default")
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
-                dir: OsString::from("root"),
+                dir: tmp_dir.path().as_os_str().to_os_string(),
+                name: "package_name".to_string(),
+                path: PathBuf::from("."),
+                root: true,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: None,
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "otherDependencies".to_string(),
                     OsString::from("WinstonMDP.githubOtherFiles.branch.with_dependencies")
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }]
         ]
     );
@@ -794,6 +1361,7 @@ fn install_t_8() {
 }
 
 #[test]
+#[allow(clippy::too_many_lines)]
 fn install_t_9() {
     let tmp_dir = tempfile::tempdir().unwrap();
     fs::write(
@@ -810,7 +1378,17 @@ fn install_t_9() {
     .unwrap();
     let deps_dir = tmp_dir.path().join("deps");
     fs::create_dir(&deps_dir).unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
     assert_unord_eq(
         &LockFile::new(tmp_dir.path()).unwrap().locks,
         &[
@@ -830,25 +1408,67 @@ fn install_t_9() {
             vec![
                 BuildUnit {
                     dir: OsString::from("WinstonMDP.githubOtherFiles.branch.cyclic_1"),
+                    name: "otherFiles".to_string(),
+                    path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.cyclic_1"),
+                    root: false,
+                    member: false,
+                    dev: false,
+                    groups: BTreeSet::new(),
+                    lock: Some(LockUnit {
+                        repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                        lock_type: Branch("cyclic_1".to_string()),
+                    }),
+                    version: None,
+                    options: BTreeSet::new(),
                     name_map: BTreeMap::from([(
                         "otherFiles".to_string(),
                         OsString::from("WinstonMDP.githubOtherFiles.branch.cyclic_2")
-                    )])
+                    )]),
+                    dev_deps: BTreeSet::new(),
+                    group_deps: BTreeMap::new(),
+                    dep_reqs: BTreeMap::new()
                 },
                 BuildUnit {
                     dir: OsString::from("WinstonMDP.githubOtherFiles.branch.cyclic_2"),
+                    name: "otherFiles".to_string(),
+                    path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.cyclic_2"),
+                    root: false,
+                    member: false,
+                    dev: false,
+                    groups: BTreeSet::new(),
+                    lock: Some(LockUnit {
+                        repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                        lock_type: Branch("cyclic_2".to_string()),
+                    }),
+                    version: None,
+                    options: BTreeSet::new(),
                     name_map: BTreeMap::from([(
                         "otherFiles".to_string(),
                         OsString::from("WinstonMDP.githubOtherFiles.branch.cyclic_1")
-                    )])
+                    )]),
+                    dev_deps: BTreeSet::new(),
+                    group_deps: BTreeMap::new(),
+                    dep_reqs: BTreeMap::new()
                 },
             ],
             vec![BuildUnit {
-                dir: OsString::from("root"),
+                dir: tmp_dir.path().as_os_str().to_os_string(),
+                name: "package_name".to_string(),
+                path: PathBuf::from("."),
+                root: true,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: None,
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "cycle".to_string(),
                     OsString::from("WinstonMDP.githubOtherFiles.branch.cyclic_1")
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }]
         ]
     );
@@ -881,7 +1501,17 @@ fn install_t_10() {
     .unwrap();
     let deps_dir = tmp_dir.path().join("deps");
     fs::create_dir(&deps_dir).unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap_err();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap_err();
 }
 
 #[test]
@@ -900,7 +1530,17 @@ fn install_t_11() {
     .unwrap();
     let deps_dir = tmp_dir.path().join("deps");
     fs::create_dir(&deps_dir).unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
     assert_unord_eq(
         &LockFile::new(tmp_dir.path()).unwrap().locks,
         &[{
@@ -917,15 +1557,49 @@ fn install_t_11() {
                 dir: OsString::from(
                     "WinstonMDP.githubOtherFiles.commit.909896f5646b7fd9f058dcd21961b8d5599dec3b"
                 ),
-                name_map: BTreeMap::new()
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.commit.909896f5646b7fd9f058dcd21961b8d5599dec3b"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Commit("909896f5646b7fd9f058dcd21961b8d5599dec3b".to_string()),
+                }),
+                version: Some(Version::new(1, 3, 5)),
+                options: BTreeSet::new(),
+                name_map: BTreeMap::new(),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
-                dir: OsString::from("root"),
+                dir: tmp_dir.path().as_os_str().to_os_string(),
+                name: "package_name".to_string(),
+                path: PathBuf::from("."),
+                root: true,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: None,
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "otherFiles".to_string(),
                     OsString::from(
                         "WinstonMDP.githubOtherFiles.commit.909896f5646b7fd9f058dcd21961b8d5599dec3b"
                     )
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::from([(
+                    "otherFiles".to_string(),
+                    DepReq {
+                        option_name: None,
+                        when: None,
+                        version: Some(semver::VersionReq::parse("1.0.0").unwrap())
+                    }
                 )])
             }]
         ]
@@ -952,10 +1626,21 @@ fn install_t_12() {
     .unwrap();
     let deps_dir = tmp_dir.path().join("deps");
     fs::create_dir(&deps_dir).unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap_err();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap_err();
 }
 
 #[test]
+#[allow(clippy::too_many_lines)]
 fn install_t_13() {
     let tmp_dir = tempfile::tempdir().unwrap();
     fs::write(
@@ -971,7 +1656,17 @@ fn install_t_13() {
     .unwrap();
     let deps_dir = tmp_dir.path().join("deps");
     fs::create_dir(&deps_dir).unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
     assert_unord_eq(
         &LockFile::new(tmp_dir.path()).unwrap().locks,
         &[
@@ -990,21 +1685,63 @@ fn install_t_13() {
         vec![
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.dev_dep"),
-                name_map: BTreeMap::new()
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.dev_dep"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("dev_dep".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
+                name_map: BTreeMap::new(),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.dev_dep_deps"),
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.dev_dep_deps"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("dev_dep_deps".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "otherFiles".to_string(),
                     OsString::from("WinstonMDP.githubOtherFiles.branch.dev_dep")
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
-                dir: OsString::from("root"),
+                dir: tmp_dir.path().as_os_str().to_os_string(),
+                name: "package_name".to_string(),
+                path: PathBuf::from("."),
+                root: true,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: None,
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "otherFiles".to_string(),
                     OsString::from("WinstonMDP.githubOtherFiles.branch.dev_dep_deps")
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }]
         ]
     );
@@ -1033,7 +1770,17 @@ fn install_t_14() {
     .unwrap();
     let deps_dir = tmp_dir.path().join("deps");
     fs::create_dir(&deps_dir).unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
     assert_unord_eq(
         &LockFile::new(tmp_dir.path()).unwrap().locks,
         &[LockUnit {
@@ -1046,14 +1793,41 @@ fn install_t_14() {
         vec![
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.optional_branch"),
-                name_map: BTreeMap::new()
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.optional_branch"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("optional_branch".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
+                name_map: BTreeMap::new(),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
-                dir: OsString::from("root"),
+                dir: tmp_dir.path().as_os_str().to_os_string(),
+                name: "package_name".to_string(),
+                path: PathBuf::from("."),
+                root: true,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: None,
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "otherFiles".to_string(),
                     OsString::from("WinstonMDP.githubOtherFiles.branch.optional_branch")
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }]
         ]
     );
@@ -1067,6 +1841,7 @@ fn install_t_14() {
 }
 
 #[test]
+#[allow(clippy::too_many_lines)]
 fn install_t_15() {
     let tmp_dir = tempfile::tempdir().unwrap();
     fs::write(
@@ -1083,7 +1858,17 @@ fn install_t_15() {
     .unwrap();
     let deps_dir = tmp_dir.path().join("deps");
     fs::create_dir(&deps_dir).unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
     assert_unord_eq(
         &LockFile::new(tmp_dir.path()).unwrap().locks,
         &[
@@ -1102,21 +1887,70 @@ fn install_t_15() {
         vec![
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.default"),
-                name_map: BTreeMap::new()
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.default"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("default".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::new(),
+                name_map: BTreeMap::new(),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }],
             vec![BuildUnit {
                 dir: OsString::from("WinstonMDP.githubOtherFiles.branch.optional_branch"),
+                name: "otherFiles".to_string(),
+                path: PathBuf::from("deps/WinstonMDP.githubOtherFiles.branch.optional_branch"),
+                root: false,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: Some(LockUnit {
+                    repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+                    lock_type: Branch("optional_branch".to_string()),
+                }),
+                version: None,
+                options: BTreeSet::from(["option".to_string()]),
                 name_map: BTreeMap::from([(
                     "otherFiles".to_string(),
                     OsString::from("WinstonMDP.githubOtherFiles.branch.default")
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::from([(
+                    "otherFiles".to_string(),
+                    DepReq {
+                        option_name: Some("option".to_string()),
+                        when: None,
+                        version: None
+                    }
                 )])
             }],
             vec![BuildUnit {
-                dir: OsString::from("root"),
+                dir: tmp_dir.path().as_os_str().to_os_string(),
+                name: "package_name".to_string(),
+                path: PathBuf::from("."),
+                root: true,
+                member: false,
+                dev: false,
+                groups: BTreeSet::new(),
+                lock: None,
+                version: None,
+                options: BTreeSet::new(),
                 name_map: BTreeMap::from([(
                     "otherFiles".to_string(),
                     OsString::from("WinstonMDP.githubOtherFiles.branch.optional_branch")
-                )])
+                )]),
+                dev_deps: BTreeSet::new(),
+                group_deps: BTreeMap::new(),
+                dep_reqs: BTreeMap::new()
             }]
         ]
     );
@@ -1130,80 +1964,2541 @@ fn install_t_15() {
 }
 
 #[test]
-fn clean_t_1() {
+fn install_t_16() {
     let tmp_dir = tempfile::tempdir().unwrap();
-    let cfg = tmp_dir.path().join(CFG_FILE_NAME);
     fs::write(
-        &cfg,
+        tmp_dir.path().join(CFG_FILE_NAME),
         r#"
-            name = "package_name"
+        name = "package_name"
 
-            [[deps]]
-            repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
-            "#,
+        [options]
+        root_option = []
+
+        [[deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+
+        [[dev_deps]]
+        name = "dev_dep"
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "b"
+        "#,
     )
     .unwrap();
     let deps_dir = tmp_dir.path().join("deps");
-    fs::create_dir(&deps_dir).unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::from(["root_option".to_string()]),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    let root = build_file(tmp_dir.path()).pop().unwrap().pop().unwrap();
+    assert!(root.root);
+    assert_eq!(root.dir, tmp_dir.path().as_os_str());
+    assert_eq!(root.name, "package_name");
+    assert_eq!(root.options, BTreeSet::from(["root_option".to_string()]));
+    assert_eq!(root.dev_deps, BTreeSet::from(["dev_dep".to_string()]));
+    assert_eq!(root.name_map.len(), 2);
+    assert_eq!(
+        LockFile::new(tmp_dir.path()).unwrap().root_dev_deps[0].name,
+        Some("dev_dep".to_string())
+    );
+}
+
+#[test]
+fn tree_t_1() {
+    let tmp_dir = tempfile::tempdir().unwrap();
     fs::write(
-        cfg,
+        tmp_dir.path().join(CFG_FILE_NAME),
         r#"
-            name = "package_name"
-            "#,
+        name = "package_name"
+
+        [[deps]]
+        name = "cycle"
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "cyclic_1"
+
+        [[deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "with_dependencies"
+
+        [[dev_deps]]
+        name = "dev_dep"
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "b"
+        "#,
     )
     .unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
-    clean(
-        &LockFile::new(tmp_dir.path()).unwrap().locks,
+    let deps_dir = tmp_dir.path().join("deps");
+    cfg_install(
+        tmp_dir.path(),
         &deps_dir,
-        &mut empty(),
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
     )
     .unwrap();
-    assert!(!Path::exists(
-        &deps_dir.join("WinstonMDP.githubOtherFiles.branch.default")
-    ));
-    assert_eq!(nfiles(&deps_dir), 0);
+    let dep_graph = graph::DepGraph::new(BuildFile::new(tmp_dir.path()).unwrap()).unwrap();
+    let tree = |options| {
+        let mut buffer = vec![];
+        graph::tree(&dep_graph, &options, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    };
+    assert_eq!(
+        tree(graph::TreeOptions::default()),
+        "package_name\n├── cycle = otherFiles branch cyclic_1 (cycle 1)\n│   └── otherFiles branch cyclic_2 (cycle 1)\n│       └── otherFiles branch cyclic_1 (cycle 1) (*)\n├── dev_dep = otherFiles branch b (dev)\n└── otherDependencies branch with_dependencies\n    └── otherFiles branch default\n"
+    );
+    assert_eq!(
+        tree(graph::TreeOptions {
+            depth: Some(1),
+            edges: Some(graph::DepKind::Normal),
+            ..Default::default()
+        }),
+        "package_name\n├── cycle = otherFiles branch cyclic_1 (cycle 1)\n└── otherDependencies branch with_dependencies\n"
+    );
+    assert_eq!(
+        tree(graph::TreeOptions {
+            invert: Some("WinstonMDP.githubOtherFiles.branch.default".to_string()),
+            ..Default::default()
+        }),
+        "otherFiles branch default\n└── otherDependencies branch with_dependencies\n    └── package_name\n"
+    );
+    assert_eq!(
+        tree(graph::TreeOptions {
+            duplicates: true,
+            depth: Some(1),
+            ..Default::default()
+        }),
+        "otherFiles branch b\n└── package_name (dev)\n\notherFiles branch cyclic_1 (cycle 1)\n├── package_name\n└── otherFiles branch cyclic_2 (cycle 1)\n\notherFiles branch cyclic_2 (cycle 1)\n└── otherFiles branch cyclic_1 (cycle 1)\n\notherFiles branch default\n└── otherDependencies branch with_dependencies\n\notherDependencies branch with_dependencies\n└── package_name\n"
+    );
+    let tmp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"
+        name = "package_name"
+
+        [[deps]]
+        repo = "https://a.git"
+        subdir = "packages/core"
+
+        [[deps]]
+        repo = "https://a.git"
+        subdir = "packages/http"
+        "#,
+    )
+    .unwrap();
+    let installer = |_: &Path, dep_dir_path: &Path, _: &LockUnit| {
+        for name in ["core", "http"] {
+            let dir = dep_dir_path.join("packages").join(name);
+            fs::create_dir_all(&dir)?;
+            fs::write(dir.join(CFG_FILE_NAME), format!("name = \"{name}\""))?;
+        }
+        Ok(())
+    };
+    cfg_install(
+        tmp_dir.path(),
+        &tmp_dir.path().join("deps"),
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &installer,
+    )
+    .unwrap();
+    let dep_graph = graph::DepGraph::new(BuildFile::new(tmp_dir.path()).unwrap()).unwrap();
+    assert!(dep_graph.duplicates().is_empty());
 }
 
 #[test]
-fn clean_t_2() {
+fn graph_t_1() {
     let tmp_dir = tempfile::tempdir().unwrap();
-    let cfg = tmp_dir.path().join(CFG_FILE_NAME);
     fs::write(
-        &cfg,
+        tmp_dir.path().join(CFG_FILE_NAME),
         r#"
-            name = "package_name"
+        name = "package_name"
 
-            [[deps]]
-            repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        [[deps]]
+        name = 'cyclé "1"'
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "cyclic_1"
 
-            [[deps]]
-            name = "package_name_to_clean"
-            repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
-            branch = "b"
-            "#,
+        [[dev_deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        commit = "909896f5646b7fd9f058dcd21961b8d5599dec3b"
+        "#,
     )
     .unwrap();
     let deps_dir = tmp_dir.path().join("deps");
-    fs::create_dir(&deps_dir).unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
-    assert!(Path::exists(
-        &deps_dir.join("WinstonMDP.githubOtherFiles.branch.default")
-    ));
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    let dep_graph = graph::DepGraph::new(BuildFile::new(tmp_dir.path()).unwrap()).unwrap();
+    let mut buffer = vec![];
+    graph::dot(&dep_graph, &mut buffer).unwrap();
+    assert_eq!(
+        String::from_utf8(buffer).unwrap(),
+        "digraph deps {\n    n0 [label=\"package_name\"];\n    n1 [label=\"otherFiles\\nhttps://github.com/WinstonMDP/githubOtherFiles.git\\nbranch cyclic_1\"];\n    n2 [label=\"otherFiles\\nhttps://github.com/WinstonMDP/githubOtherFiles.git\\nbranch cyclic_2\"];\n    n3 [label=\"otherFiles\\nhttps://github.com/WinstonMDP/githubOtherFiles.git\\ncommit 909896f5646b7fd9f058dcd21961b8d5599dec3b\"];\n    subgraph cluster_1 { label=\"cycle 1\"; n1; n2; }\n    n0 -> n1 [label=\"cyclé \\\"1\\\"\"];\n    n0 -> n3 [label=\"otherFiles\", style=dashed];\n    n1 -> n2 [label=\"otherFiles\"];\n    n2 -> n1 [label=\"otherFiles\"];\n}\n"
+    );
+    let mut buffer = vec![];
+    graph::json(&dep_graph, &mut buffer).unwrap();
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(&buffer).unwrap()["edges"],
+        serde_json::json!([
+            {"from": 0, "to": 1, "name": "cyclé \"1\"", "dev": false},
+            {"from": 0, "to": 3, "name": "otherFiles", "dev": true},
+            {"from": 1, "to": 2, "name": "otherFiles", "dev": false},
+            {"from": 2, "to": 1, "name": "otherFiles", "dev": false}
+        ])
+    );
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(&buffer).unwrap()["nodes"][1],
+        serde_json::json!({
+            "id": 1,
+            "name": "otherFiles",
+            "root": false,
+            "repo": "https://github.com/WinstonMDP/githubOtherFiles.git",
+            "branch": "cyclic_1",
+            "version": null,
+            "options": [],
+            "cycle": 1
+        })
+    );
+}
+
+#[test]
+fn why_t_1() {
+    let tmp_dir = tempfile::tempdir().unwrap();
     fs::write(
-        cfg,
+        tmp_dir.path().join(CFG_FILE_NAME),
         r#"
-            name = "package_name"
+        name = "package_name"
 
-            [[deps]]
-            repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
-            "#,
+        [[deps]]
+        name = "optional"
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "optional_branch"
+        options = ["option"]
+
+        [[deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "with_dependencies"
+        "#,
+    )
+    .unwrap();
+    let deps_dir = tmp_dir.path().join("deps");
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    let dep_graph = graph::DepGraph::new(BuildFile::new(tmp_dir.path()).unwrap()).unwrap();
+    let mut buffer = vec![];
+    graph::why(
+        &dep_graph,
+        "WinstonMDP.githubOtherFiles.branch.default",
+        &mut buffer,
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(buffer).unwrap(),
+        "package_name\n└── optional = otherFiles branch optional_branch [option]\n    └── otherFiles branch default (option_name = \"option\")\n\npackage_name\n└── otherDependencies branch with_dependencies\n    └── otherFiles branch default\n"
+    );
+    graph::why(&dep_graph, "nothing", &mut empty()).unwrap_err();
+}
+
+#[test]
+fn install_t_17() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let cfg = tmp_dir.path().join(CFG_FILE_NAME);
+    fs::write(
+        &cfg,
+        r#"
+        name = "package_name"
+
+        [[deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "with_options"
+        "#,
+    )
+    .unwrap();
+    let deps_dir = tmp_dir.path().join("deps");
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    let sccs = build_file(tmp_dir.path());
+    assert_eq!(sccs.len(), 2);
+    assert_eq!(
+        sccs[0][0].options,
+        BTreeSet::from(["default".to_string(), "json".to_string()])
+    );
+    assert!(!Path::exists(
+        &deps_dir.join("WinstonMDP.githubOtherFiles.branch.default")
+    ));
+    fs::write(
+        &cfg,
+        r#"
+        name = "package_name"
+
+        [[deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "with_options"
+        options = ["full"]
+        "#,
+    )
+    .unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    let sccs = build_file(tmp_dir.path());
+    assert_eq!(sccs.len(), 3);
+    assert_eq!(
+        sccs[1][0].options,
+        BTreeSet::from([
+            "default".to_string(),
+            "full".to_string(),
+            "json".to_string(),
+            "net".to_string()
+        ])
+    );
+    assert!(Path::exists(
+        &deps_dir.join("WinstonMDP.githubOtherFiles.branch.default")
+    ));
+}
+
+#[test]
+fn install_t_18() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let cfg = tmp_dir.path().join(CFG_FILE_NAME);
+    fs::write(
+        &cfg,
+        r#"
+        name = "package_name"
+
+        [[deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "with_options"
+        options = ["nett"]
+        "#,
+    )
+    .unwrap();
+    let deps_dir = tmp_dir.path().join("deps");
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap_err();
+    fs::write(
+        &cfg,
+        r#"
+        name = "package_name"
+
+        [options]
+        full = ["undeclared"]
+        "#,
+    )
+    .unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap_err();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::from(["option".to_string()]),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap_err();
+}
+
+#[test]
+fn install_t_19() {
+    let with_options = r#"
+        [[deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "with_options"
+        "#;
+    let enables_net = r#"
+        [[deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "enables_net"
+        "#;
+    for cfg in [
+        format!("name = \"package_name\"\n{with_options}\n{enables_net}"),
+        format!("name = \"package_name\"\n{enables_net}\n{with_options}"),
+    ] {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        fs::write(tmp_dir.path().join(CFG_FILE_NAME), cfg).unwrap();
+        let deps_dir = tmp_dir.path().join("deps");
+        cfg_install(
+            tmp_dir.path(),
+            &deps_dir,
+            &HashSet::new(),
+            &HashSet::new(),
+            false,
+            &BTreeSet::new(),
+            false,
+            &stub_installer,
+        )
+        .unwrap();
+        let units: Vec<BuildUnit> = build_file(tmp_dir.path()).concat();
+        let with_options_units: Vec<&BuildUnit> = units
+            .iter()
+            .filter(|x| x.dir == "WinstonMDP.githubOtherFiles.branch.with_options")
+            .collect();
+        assert_eq!(with_options_units.len(), 1);
+        assert_eq!(
+            with_options_units[0].options,
+            BTreeSet::from(["default".to_string(), "json".to_string(), "net".to_string()])
+        );
+        assert_eq!(
+            with_options_units[0].name_map,
+            BTreeMap::from([(
+                "otherFiles".to_string(),
+                OsString::from("WinstonMDP.githubOtherFiles.branch.default")
+            )])
+        );
+        assert_eq!(units.len(), 4);
+        assert_eq!(LockFile::new(tmp_dir.path()).unwrap().locks.len(), 3);
+    }
+}
+
+#[test]
+fn install_t_20() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let cfg = tmp_dir.path().join(CFG_FILE_NAME);
+    fs::write(
+        &cfg,
+        r#"
+        name = "package_name"
+
+        [options]
+        full = ["parser/net"]
+
+        [[deps]]
+        name = "parser"
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "with_options"
+        "#,
+    )
+    .unwrap();
+    let deps_dir = tmp_dir.path().join("deps");
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::from(["full".to_string()]),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    let sccs = build_file(tmp_dir.path());
+    assert_eq!(sccs.len(), 3);
+    assert_eq!(
+        sccs[1][0].options,
+        BTreeSet::from(["default".to_string(), "json".to_string(), "net".to_string()])
+    );
+    assert_eq!(sccs[2][0].options, BTreeSet::from(["full".to_string()]));
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::from(["parser/json".to_string()]),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    assert_eq!(build_file(tmp_dir.path()).len(), 2);
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::from(["parser/undeclared".to_string()]),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap_err();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::from(["lexer/net".to_string()]),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap_err();
+}
+
+#[test]
+fn install_t_21() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"
+        name = "package_name"
+
+        [[deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "with_dependencies"
+
+        [[dev_deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "b"
+
+        [[dev_deps]]
+        name = "shared"
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        "#,
+    )
+    .unwrap();
+    let deps_dir = tmp_dir.path().join("deps");
+    let b_lock = LockUnit {
+        repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+        lock_type: Branch("b".to_string()),
+    };
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    let lock_file = LockFile::new(tmp_dir.path()).unwrap();
+    assert_eq!(lock_file.locks.len(), 2);
+    assert_eq!(lock_file.dev_locks, std::slice::from_ref(&b_lock));
+    assert!(!lock_file.root_no_dev);
+    let dev_dirs: Vec<OsString> = build_file(tmp_dir.path())
+        .into_iter()
+        .flatten()
+        .filter(|x| x.dev)
+        .map(|x| x.dir)
+        .collect();
+    assert_eq!(dev_dirs, [dep_dir(&b_lock).unwrap()]);
+    let mut buffer = vec![];
+    clean(&lock_file.locks, tmp_dir.path(), &deps_dir, &mut buffer).unwrap();
+    assert_eq!(nfiles(&deps_dir), 2);
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        true,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    let lock_file = LockFile::new(tmp_dir.path()).unwrap();
+    assert_eq!(lock_file.locks.len(), 2);
+    assert!(lock_file.dev_locks.is_empty());
+    assert!(lock_file.root_dev_deps.is_empty());
+    assert!(lock_file.root_no_dev);
+    assert_eq!(nfiles(&deps_dir), 2);
+    // A reinstall with ``--no-dev`` of the last install doesn't clone dev deps.
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &lock_file.root_options,
+        &lock_file.root_cfgs,
+        lock_file.root_no_dev,
+        &lock_file.root_groups.into_keys().collect(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    assert!(LockFile::new(tmp_dir.path()).unwrap().dev_locks.is_empty());
+    assert!(build_file(tmp_dir.path())
+        .into_iter()
+        .flatten()
+        .all(|x| !x.dev));
+}
+
+#[test]
+fn install_t_22() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"
+        name = "package_name"
+
+        [[deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+
+        [[groups.bench.deps]]
+        name = "bench_dep"
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "b"
+
+        [[groups.doc.deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "with_dependencies"
+        "#,
+    )
+    .unwrap();
+    let deps_dir = tmp_dir.path().join("deps");
+    let lock = |branch: &str| LockUnit {
+        repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+        lock_type: Branch(branch.to_string()),
+    };
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    let lock_file = LockFile::new(tmp_dir.path()).unwrap();
+    assert_eq!(lock_file.locks, [lock("default")]);
+    assert!(lock_file.group_locks.is_empty());
+    assert_eq!(nfiles(&deps_dir), 1);
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::from(["bench".to_string(), "doc".to_string()]),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    let lock_file = LockFile::new(tmp_dir.path()).unwrap();
+    assert_eq!(lock_file.locks, [lock("default")]);
+    assert_eq!(
+        lock_file.group_locks,
+        BTreeMap::from([
+            ("bench".to_string(), vec![lock("b")]),
+            ("doc".to_string(), vec![lock("with_dependencies")]),
+        ])
+    );
+    assert_eq!(
+        lock_file.root_groups.keys().collect::<Vec<_>>(),
+        ["bench", "doc"]
+    );
+    let root = build_file(tmp_dir.path()).pop().unwrap().pop().unwrap();
+    assert_eq!(
+        root.group_deps,
+        BTreeMap::from([
+            (
+                "bench".to_string(),
+                BTreeSet::from(["bench_dep".to_string()])
+            ),
+            (
+                "doc".to_string(),
+                BTreeSet::from(["otherDependencies".to_string()])
+            ),
+        ])
+    );
+    assert!(cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::from(["tooling".to_string()]),
+        false,
+        &stub_installer,
+    )
+    .is_err());
+}
+
+#[test]
+fn expr_t_1() {
+    let cfgs = expr::host_cfgs(&HashSet::from([
+        "flag".to_string(),
+        "key = \"value\"".to_string(),
+    ]));
+    let eval = |s: &str| expr::Expr::parse_cfg(s).unwrap().eval_cfgs(&cfgs);
+    assert!(eval("cfg(flag)"));
+    assert!(eval("cfg(key = \"value\")"));
+    assert!(!eval("cfg(key = \"other\")"));
+    assert!(eval("cfg(any(other, all(flag, not(other))))"));
+    assert!(eval("cfg(all())"));
+    assert!(!eval("cfg(any())"));
+    assert!(eval(&format!(
+        "cfg(target_os = {:?})",
+        std::env::consts::OS
+    )));
+    assert_eq!(
+        expr::Expr::parse("not(a)").unwrap(),
+        expr::Expr::Not(Box::new(expr::Expr::Name("a".to_string())))
+    );
+    assert!(expr::Expr::parse("not(a, b)").is_err());
+    assert!(expr::Expr::parse("one(a)").is_err());
+    assert!(expr::Expr::parse("a b").is_err());
+    assert!(expr::Expr::parse_cfg("x86_64-unknown-linux-gnu").is_err());
+}
+
+#[test]
+fn install_t_23() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"
+        name = "package_name"
+
+        [[target.'cfg(flag)'.deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "b"
+
+        [[target.'cfg(not(flag))'.deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "main"
+
+        [[target.'cfg(wasm)'.deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "with_dependencies"
+        "#,
+    )
+    .unwrap();
+    let deps_dir = tmp_dir.path().join("deps");
+    let lock = |branch: &str| LockUnit {
+        repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+        lock_type: Branch(branch.to_string()),
+    };
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    let target_locks = BTreeMap::from([
+        ("cfg(flag)".to_string(), vec![lock("b")]),
+        ("cfg(not(flag))".to_string(), vec![lock("main")]),
+        (
+            "cfg(wasm)".to_string(),
+            vec![lock("default"), lock("with_dependencies")],
+        ),
+    ]);
+    let lock_file = LockFile::new(tmp_dir.path()).unwrap();
+    assert!(lock_file.locks.is_empty());
+    assert_eq!(lock_file.target_locks, target_locks);
+    assert_eq!(lock_file.root_deps.len(), 3);
+    assert_eq!(nfiles(&deps_dir), 4);
+    let build_locks = |build_file: Vec<Vec<BuildUnit>>| {
+        build_file
+            .into_iter()
+            .flatten()
+            .filter_map(|x| x.lock)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(build_locks(build_file(tmp_dir.path())), [lock("main")]);
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::from(["flag".to_string()]),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    let lock_file = LockFile::new(tmp_dir.path()).unwrap();
+    assert!(lock_file.locks.is_empty());
+    assert_eq!(lock_file.target_locks, target_locks);
+    assert_eq!(build_locks(build_file(tmp_dir.path())), [lock("b")]);
+    assert_eq!(lock_file.root_cfgs, HashSet::from(["flag".to_string()]));
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"
+        name = "package_name"
+
+        [[target.x86_64-unknown-linux-gnu.deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        "#,
+    )
+    .unwrap();
+    assert!(Cfg::new(tmp_dir.path()).is_err());
+}
+
+#[test]
+fn install_t_24() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let cfg = |when: &str| {
+        format!(
+            r#"
+            name = "package_name"
+
+            [options]
+            json = []
+            yaml = []
+            net = []
+            wasm = []
+
+            [[deps]]
+            repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+            branch = "b"
+            when = "any(json, yaml)"
+
+            [[deps]]
+            repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+            branch = "main"
+            when = "{when}"
+            "#
+        )
+    };
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        cfg("all(net, not(wasm))"),
+    )
+    .unwrap();
+    let deps_dir = tmp_dir.path().join("deps");
+    let branches = |options: &[&str]| {
+        cfg_install(
+            tmp_dir.path(),
+            &deps_dir,
+            &options.iter().map(ToString::to_string).collect(),
+            &HashSet::new(),
+            false,
+            &BTreeSet::new(),
+            false,
+            &stub_installer,
+        )?;
+        Ok::<_, anyhow::Error>(
+            LockFile::new(tmp_dir.path())?
+                .locks
+                .into_iter()
+                .map(|x| match x.lock_type {
+                    Branch(branch) => branch,
+                    Commit(commit) => commit,
+                })
+                .collect::<BTreeSet<_>>(),
+        )
+    };
+    assert_eq!(
+        branches(&["yaml"]).unwrap(),
+        BTreeSet::from(["b".to_string()])
+    );
+    assert_eq!(
+        branches(&["net"]).unwrap(),
+        BTreeSet::from(["main".to_string()])
+    );
+    assert_eq!(
+        branches(&["json", "net", "wasm"]).unwrap(),
+        BTreeSet::from(["b".to_string()])
+    );
+    let root = build_file(tmp_dir.path()).pop().unwrap().pop().unwrap();
+    assert_eq!(
+        root.dep_reqs["otherFiles"].when,
+        Some("any(json, yaml)".to_string())
+    );
+    fs::write(tmp_dir.path().join(CFG_FILE_NAME), cfg("any(jsn)")).unwrap();
+    assert!(format!("{:#}", branches(&[]).unwrap_err()).contains("undeclared \"jsn\" option"));
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        cfg(r#"target_os = \"linux\""#),
+    )
+    .unwrap();
+    assert!(branches(&[]).is_err());
+}
+
+#[test]
+#[allow(clippy::too_many_lines)]
+fn install_t_25() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let core_dir = tmp_dir.path().join("packages").join("core");
+    let http_dir = tmp_dir.path().join("packages").join("http");
+    fs::create_dir_all(&core_dir).unwrap();
+    fs::create_dir_all(&http_dir).unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"
+        name = "workspace_name"
+
+        [workspace]
+        members = ["packages/core", "packages/http"]
+        "#,
+    )
+    .unwrap();
+    fs::write(
+        core_dir.join(CFG_FILE_NAME),
+        r#"
+        name = "core"
+
+        [[deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+
+        [[dev_deps]]
+        name = "dev_dep"
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "b"
+        "#,
+    )
+    .unwrap();
+    fs::write(
+        http_dir.join(CFG_FILE_NAME),
+        r#"
+        name = "http"
+
+        [[deps]]
+        path = "../core"
+
+        [[deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "main"
+        "#,
+    )
+    .unwrap();
+    let deps_dir = tmp_dir.path().join("deps");
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    let lock = |branch: &str| LockUnit {
+        repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+        lock_type: Branch(branch.to_string()),
+    };
+    let lock_file = LockFile::new(tmp_dir.path()).unwrap();
+    assert_unord_eq(&lock_file.locks, &[lock("default"), lock("main")]);
+    assert_eq!(lock_file.dev_locks, [lock("b")]);
+    assert_eq!(nfiles(&deps_dir), 3);
+    assert!(!core_dir.join(LOCK_FILE_NAME).exists());
+    let units: Vec<BuildUnit> = build_file(tmp_dir.path()).into_iter().flatten().collect();
+    let http = units.iter().find(|x| x.name == "http").unwrap();
+    assert!(http.member);
+    assert_eq!(http.path, Path::new("packages").join("http"));
+    assert_eq!(
+        http.name_map["core"],
+        fs::canonicalize(&core_dir).unwrap().into_os_string()
+    );
+    let core = units.iter().find(|x| x.name == "core").unwrap();
+    assert!(core.member);
+    assert!(core.lock.is_none());
+    assert_eq!(core.dev_deps, BTreeSet::from(["dev_dep".to_string()]));
+    let member_units: Vec<BuildUnit> = build_file(&http_dir).into_iter().flatten().collect();
+    assert_eq!(member_units.len(), 5);
+    let http = member_units.iter().find(|x| x.name == "http").unwrap();
+    assert!(http.root);
+    assert_eq!(http.path, Path::new("."));
+    assert_eq!(
+        member_units.iter().find(|x| x.dev).unwrap().path,
+        deps_dir.join(dep_dir(&lock("b")).unwrap())
+    );
+    assert_eq!(workspace_root(&core_dir).unwrap(), tmp_dir.path());
+    assert_eq!(
+        member_dir(tmp_dir.path(), "http").unwrap(),
+        fs::canonicalize(&http_dir).unwrap()
+    );
+    assert!(member_dir(tmp_dir.path(), "db").is_err());
+    fs::write(
+        http_dir.join(CFG_FILE_NAME),
+        r#"
+        name = "http"
+
+        [[deps]]
+        path = "../core"
+        branch = "main"
+        "#,
+    )
+    .unwrap();
+    assert!(Cfg::new(&http_dir).is_err());
+}
+
+#[test]
+fn install_t_26() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"
+        name = "package_name"
+
+        [[deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "monorepo"
+        subdir = "packages/core"
+
+        [[deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "monorepo"
+        subdir = "packages/http"
+        "#,
+    )
+    .unwrap();
+    let deps_dir = tmp_dir.path().join("deps");
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    let lock = |branch: &str| LockUnit {
+        repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+        lock_type: Branch(branch.to_string()),
+    };
+    assert_unord_eq(
+        &LockFile::new(tmp_dir.path()).unwrap().locks,
+        &[lock("monorepo"), lock("default"), lock("main")],
+    );
+    assert_eq!(nfiles(&deps_dir), 3);
+    let root = build_file(tmp_dir.path()).pop().unwrap().pop().unwrap();
+    let monorepo_dir = Path::new(&dep_dir(&lock("monorepo")).unwrap()).join("packages");
+    assert_eq!(
+        root.name_map,
+        BTreeMap::from([
+            (
+                "core".to_string(),
+                monorepo_dir.join("core").into_os_string()
+            ),
+            (
+                "http".to_string(),
+                monorepo_dir.join("http").into_os_string()
+            ),
+        ])
+    );
+    let http = build_file(tmp_dir.path())
+        .into_iter()
+        .flatten()
+        .find(|x| x.name == "http")
+        .unwrap();
+    assert_eq!(http.path, Path::new("deps").join(monorepo_dir.join("http")));
+    assert_eq!(http.lock, Some(lock("monorepo")));
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"
+        name = "package_name"
+
+        [[deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        subdir = "../packages"
+        "#,
+    )
+    .unwrap();
+    assert!(Cfg::new(tmp_dir.path()).is_err());
+}
+
+#[test]
+fn install_t_27() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"
+        name = "package_name"
+        deps_dir = "target/deps"
+
+        [[deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        deps_dir(tmp_dir.path(), Some(PathBuf::from("/other"))).unwrap(),
+        PathBuf::from("/other")
+    );
+    let deps_dir = deps_dir(tmp_dir.path(), None).unwrap();
+    assert_eq!(deps_dir, tmp_dir.path().join("target").join("deps"));
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    assert_eq!(nfiles(&deps_dir), 1);
+    let build_file = BuildFile::new(tmp_dir.path()).unwrap();
+    assert_eq!(build_file.deps_dir, Path::new("target").join("deps"));
+    assert_eq!(
+        build_file.sccs[0][0].path,
+        Path::new("target")
+            .join("deps")
+            .join("WinstonMDP.githubOtherFiles.branch.default")
+    );
+    for dir in [".", "..", "target/.."] {
+        assert!(super::deps_dir(tmp_dir.path(), Some(PathBuf::from(dir))).is_err());
+    }
+    let other_dir = tempfile::tempdir().unwrap();
+    assert!(super::deps_dir(tmp_dir.path(), Some(other_dir.path().to_path_buf())).is_ok());
+    assert!(clean(&[], tmp_dir.path(), other_dir.path(), &mut empty()).is_err());
+    assert!(clean(&[], tmp_dir.path(), tmp_dir.path(), &mut empty()).is_err());
+    clean(&[], tmp_dir.path(), &deps_dir, &mut empty()).unwrap();
+    assert_eq!(nfiles(&deps_dir), 0);
+}
+
+#[test]
+fn cfg_t_1() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"
+        name = "package_name"
+        version = "0.1.0"
+        authors = ["Author <author@example.com>"]
+        license = "(MIT OR Apache-2.0) AND GPL-2.0+ WITH Classpath-exception-2.0"
+        description = "A package."
+        homepage = "https://example.com"
+        keywords = ["parser", "no-std"]
+        "#,
+    )
+    .unwrap();
+    Cfg::new(tmp_dir.path()).unwrap();
+    for (key, value) in [
+        ("name", r#""1package""#),
+        ("version", r#""0.1""#),
+        ("license", r#""MIT OR""#),
+        ("license", r#""(MIT""#),
+        ("homepage", r#""ftp://example.com""#),
+        ("keywords", r#"["no std"]"#),
+    ] {
+        let cfg = if key == "name" {
+            format!("name = {value}\n")
+        } else {
+            format!("name = \"package_name\"\n{key} = {value}\n")
+        };
+        fs::write(tmp_dir.path().join(CFG_FILE_NAME), cfg).unwrap();
+        let error = format!("{:#}", Cfg::new(tmp_dir.path()).unwrap_err());
+        let line = if key == "name" { 1 } else { 2 };
+        assert!(error.contains(&format!("line {line}, column")), "{error}");
+    }
+}
+
+#[test]
+fn cfg_t_2() {
+    assert_eq!(
+        manifest::check_keys(
+            r#"name = "package_name"
+nmae = "package_name"
+
+[[deps]]
+repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+brnach = "b"
+option = "net"
+
+[target.'cfg(unix)']
+deps = [{ repo = "https://github.com/WinstonMDP/githubOtherFiles.git", xyz = 1 }]
+"#
+        )
+        .unwrap(),
+        vec![
+            r#"Unknown "nmae" key of the root at line 2, column 1. Did you mean "name"?"#,
+            r#"Unknown "brnach" key of deps[0] at line 6, column 1. Did you mean "branch"?"#,
+            r#"Unknown "option" key of deps[0] at line 7, column 1. Did you mean "options"?"#,
+            r#"Unknown "xyz" key of target.cfg(unix).deps[0] at line 10, column 72."#,
+        ]
+    );
+    let error = manifest::check_keys(
+        r#"name = "package_name"
+
+[[dev_deps]]
+repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+branch = "b"
+commit = "c"
+"#,
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(
+        error.contains(r#"has both "branch" and "commit" keys"#),
+        "{error}"
+    );
+    assert!(error.contains("line 6, column 1"), "{error}");
+    let tmp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        "name = \"package_name\"\nintrepreter = \"/bin/sanskrit\"\n",
+    )
+    .unwrap();
+    assert!(Cfg::new(tmp_dir.path()).is_err());
+}
+
+#[test]
+fn cfg_t_3() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let member_dir = tmp_dir.path().join("member");
+    fs::create_dir(&member_dir).unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"name = "package_name"
+nmae = "package_name"
+
+[workspace]
+members = ["member"]
+
+[[deps]]
+branch = "b"
+
+[target.'x86_64']
+deps = [{ path = "../a", subdir = "b" }]
+"#,
+    )
+    .unwrap();
+    fs::write(
+        member_dir.join(CFG_FILE_NAME),
+        r#"name = "member"
+
+[[dev_deps]]
+repo = "https://a.git"
+subdir = "../b"
+"#,
+    )
+    .unwrap();
+    let root_error = |x: &str| {
+        format!(
+            "Error in {:#?} cfg file. {x}",
+            tmp_dir.path().join(CFG_FILE_NAME)
+        )
+    };
+    let (errors, warnings) = manifest::check_manifest(tmp_dir.path(), false);
+    assert_eq!(
+        errors,
+        vec![
+            root_error(
+                r#"Unknown "nmae" key of the root at line 2, column 1. Did you mean "name"?"#
+            ),
+            root_error(r#"deps[0] dep at line 7, column 1 has neither "repo" nor "path" key."#),
+            root_error(&format!(
+                "target.x86_64 at line 10, column 9 isn't a valid target. {}",
+                expr::Expr::parse_cfg("x86_64").unwrap_err()
+            )),
+            root_error(
+                r#"target.x86_64.deps[0] dep at line 11, column 26 has both "path" and "subdir" keys. Use one of them."#
+            ),
+            format!(
+                "Error in {:#?} cfg file. {}",
+                fs::canonicalize(&member_dir).unwrap().join(CFG_FILE_NAME),
+                r#"dev_deps[0] dep at line 5, column 1 has "../b" subdir, which isn't a relative path inside the repo."#
+            ),
+        ]
+    );
+    assert!(warnings.is_empty());
+    let (errors, warnings) = manifest::check_manifest(tmp_dir.path(), true);
+    assert_eq!(errors.len(), 4);
+    assert_eq!(warnings.len(), 1);
+    assert!(Cfg::new(&member_dir).is_err());
+}
+
+#[test]
+fn install_t_28() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let project_dir = tmp_dir.path().join("project");
+    let old_dir = tmp_dir.path().join("old");
+    fs::create_dir(&project_dir).unwrap();
+    fs::create_dir(&old_dir).unwrap();
+    let interpreter = tmp_dir.path().join("sanskrit");
+    fs::write(&interpreter, "#!/bin/sh\necho sanskrit 2.1.0\n").unwrap();
+    fs::set_permissions(
+        &interpreter,
+        std::os::unix::fs::PermissionsExt::from_mode(0o755),
+    )
+    .unwrap();
+    let write_cfgs = |interpreter_version: &str, crack_version: &str| {
+        fs::write(
+            project_dir.join(CFG_FILE_NAME),
+            format!(
+                r#"
+                name = "package_name"
+                interpreter = {interpreter:?}
+                interpreter_version = "{interpreter_version}"
+
+                [[deps]]
+                path = "../old"
+                "#
+            ),
+        )
+        .unwrap();
+        fs::write(
+            old_dir.join(CFG_FILE_NAME),
+            format!("name = \"old\"\ncrack_version = \"{crack_version}\"\n"),
+        )
+        .unwrap();
+    };
+    let install = || {
+        cfg_install(
+            &project_dir,
+            &project_dir.join("deps"),
+            &HashSet::new(),
+            &HashSet::new(),
+            false,
+            &BTreeSet::new(),
+            false,
+            &stub_installer,
+        )
+    };
+    write_cfgs("^2", "<1");
+    install().unwrap();
+    write_cfgs("^3", ">=100");
+    let error = install().unwrap_err().to_string();
+    assert!(
+        error.contains(r#""old" package requires >=100 crack version"#),
+        "{error}"
+    );
+    assert!(
+        error.contains(r#""package_name" package requires ^3 interpreter version"#),
+        "{error}"
+    );
+    fs::write(
+        old_dir.join(CFG_FILE_NAME),
+        "name = \"old\"\ncrack_version = \">=100\"\nnew_key = 1\n",
+    )
+    .unwrap();
+    let error = format!("{:#}", Cfg::new(&old_dir).unwrap_err());
+    assert!(error.contains("requires >=100 crack version"), "{error}");
+}
+
+#[test]
+fn add_dep_t_1() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"# the package
+name = "package_name"
+dev_deps = []
+
+# a parser
+[[deps]]
+repo = "https://github.com/WinstonMDP/githubOtherFiles.git" # the main one
+
+[workspace]
+members = []
+"#,
+    )
+    .unwrap();
+    let dep = |repo: &str, branch: &str| Dep {
+        name: None,
+        repo: Some(repo.to_string()),
+        path: None,
+        subdir: None,
+        dep_type: Some(DepType::Branch(branch.to_string())),
+        options: None,
+        option_name: None,
+        when: None,
+        target: None,
+    };
+    manifest::add_dep(tmp_dir.path(), &dep("https://a.git", "main"), false, false).unwrap();
+    manifest::add_dep(tmp_dir.path(), &dep("https://b.git", "main"), true, false).unwrap();
+    let other_files = dep("https://github.com/WinstonMDP/githubOtherFiles.git", "b");
+    assert!(manifest::add_dep(tmp_dir.path(), &other_files, false, false).is_err());
+    manifest::add_dep(tmp_dir.path(), &other_files, false, true).unwrap();
+    assert_eq!(
+        fs::read_to_string(tmp_dir.path().join(CFG_FILE_NAME)).unwrap(),
+        r#"# the package
+name = "package_name"
+dev_deps = [{ repo = "https://b.git", branch = "main" }]
+
+# a parser
+[[deps]]
+repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+branch = "b"
+
+[[deps]]
+repo = "https://a.git"
+branch = "main"
+
+[workspace]
+members = []
+"#
+    );
+    Cfg::new(tmp_dir.path()).unwrap();
+}
+
+#[test]
+fn new_dep_t_1() {
+    let version_tags = |_: &str| {
+        Ok(["1.2.0", "1.5.1", "2.0.0-rc.1"]
+            .iter()
+            .map(|x| (Version::parse(x).unwrap(), String::new()))
+            .collect())
+    };
+    let new_dep = |new_dep| {
+        toml::Value::try_from(manifest::new_dep(new_dep, &version_tags).unwrap()).unwrap()
+    };
+    assert_eq!(
+        new_dep(manifest::NewDep {
+            repo: "https://a.git".to_string(),
+            version: Some("latest".to_string()),
+            rename: Some("a".to_string()),
+            optional: Some("net".to_string()),
+            options: vec!["json".to_string(), "fs".to_string()],
+            ..Default::default()
+        }),
+        toml::toml! {
+            name = "a"
+            repo = "https://a.git"
+            version = "^1.5.1"
+            options = ["json", "fs"]
+            option_name = "net"
+        }
+        .into()
+    );
+    assert_eq!(
+        new_dep(manifest::NewDep {
+            repo: "https://a.git".to_string(),
+            version: Some("~1.2".to_string()),
+            ..Default::default()
+        }),
+        toml::toml! {
+            repo = "https://a.git"
+            version = "~1.2"
+        }
+        .into()
+    );
+    assert_eq!(
+        new_dep(manifest::NewDep {
+            repo: "https://a.git".to_string(),
+            branch: Some("dev".to_string()),
+            ..Default::default()
+        }),
+        toml::toml! {
+            repo = "https://a.git"
+            branch = "dev"
+        }
+        .into()
+    );
+    assert_eq!(
+        new_dep(manifest::NewDep {
+            repo: "https://a.git".to_string(),
+            commit: Some("sha".to_string()),
+            ..Default::default()
+        }),
+        toml::toml! {
+            repo = "https://a.git"
+            commit = "sha"
+        }
+        .into()
+    );
+    assert!(manifest::new_dep(
+        manifest::NewDep {
+            repo: "https://a.git".to_string(),
+            version: Some("latest".to_string()),
+            ..Default::default()
+        },
+        &|_| Ok(vec![(Version::parse("1.0.0-rc.1").unwrap(), String::new())]),
+    )
+    .is_err());
+}
+
+#[test]
+#[allow(clippy::too_many_lines)]
+fn remove_deps_t_1() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"name = "package_name"
+dev_deps = [{ repo = "https://b.git" }, { repo = "https://c.git" }]
+
+# a parser
+[[deps]]
+name = "parser"
+repo = "https://a.git"
+
+# a registry dep
+[[deps]]
+repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+
+[[deps]]
+repo = "https://d.git"
+"#,
+    )
+    .unwrap();
+    let registry = HashMap::from([
+        (
+            "other_files".to_string(),
+            "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+        ),
+        ("b".to_string(), "https://b.git".to_string()),
+    ]);
+    manifest::remove_deps(
+        tmp_dir.path(),
+        &["parser".to_string(), "other_files".to_string()],
+        &registry,
+        false,
+    )
+    .unwrap();
+    manifest::remove_deps(tmp_dir.path(), &["b".to_string()], &registry, true).unwrap();
+    assert!(manifest::remove_deps(tmp_dir.path(), &["b".to_string()], &registry, false).is_err());
+    assert_eq!(
+        fs::read_to_string(tmp_dir.path().join(CFG_FILE_NAME)).unwrap(),
+        r#"name = "package_name"
+dev_deps = [{ repo = "https://c.git" }]
+
+[[deps]]
+repo = "https://d.git"
+"#
+    );
+    fs::create_dir(tmp_dir.path().join("local")).unwrap();
+    fs::write(
+        tmp_dir.path().join("local").join(CFG_FILE_NAME),
+        r#"name = "local_name""#,
+    )
+    .unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"name = "package_name"
+
+[[deps]]
+repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+
+[[deps]]
+path = "local"
+
+[[deps]]
+path = "other_local"
+
+[[groups.bench.deps]]
+repo = "https://d.git"
+
+[[target.'cfg(wasm)'.deps]]
+repo = "https://e.git"
+
+[[target.'cfg(wasm)'.dev_deps]]
+repo = "https://e.git"
+"#,
+    )
+    .unwrap();
+    fs::create_dir(tmp_dir.path().join("other_local")).unwrap();
+    fs::write(
+        tmp_dir.path().join("other_local").join(CFG_FILE_NAME),
+        r#"name = "other_local_name""#,
+    )
+    .unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &tmp_dir.path().join("deps"),
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    manifest::remove_deps(
+        tmp_dir.path(),
+        &[
+            "otherFiles".to_string(),
+            "local_name".to_string(),
+            "other_local".to_string(),
+            "https://d.git".to_string(),
+            "https://e.git".to_string(),
+        ],
+        &HashMap::new(),
+        false,
+    )
+    .unwrap();
+    assert_eq!(
+        fs::read_to_string(tmp_dir.path().join(CFG_FILE_NAME)).unwrap(),
+        r#"name = "package_name"
+
+[[target.'cfg(wasm)'.dev_deps]]
+repo = "https://e.git"
+"#
+    );
+}
+
+#[test]
+#[allow(clippy::too_many_lines)]
+fn upgrade_deps_t_1() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let cfg = r#"name = "package_name"
+
+[[deps]]
+repo = "https://a.git"
+version = "^1.2" # the parser
+
+[[deps]]
+name = "b"
+repo = "https://b.git"
+version = "~0.3"
+
+[[deps]]
+repo = "https://c.git"
+branch = "main"
+
+[[groups.bench.deps]]
+repo = "https://a.git"
+version = "=1.2.0"
+"#;
+    fs::write(tmp_dir.path().join(CFG_FILE_NAME), cfg).unwrap();
+    let version_tags = |repo: &str| {
+        Ok(match repo {
+            "https://a.git" => ["1.2.0", "1.5.1", "2.0.0", "3.0.0-rc.1"].as_slice(),
+            "https://b.git" => &["0.3.0", "0.3.4", "0.4.0"],
+            _ => panic!("{repo:?} isn't a version dep."),
+        }
+        .iter()
+        .map(|x| (Version::parse(x).unwrap(), String::new()))
+        .collect())
+    };
+    let req = |x: &str| semver::VersionReq::parse(x).unwrap();
+    assert_eq!(
+        manifest::upgrade_deps(
+            tmp_dir.path(),
+            &[],
+            &HashMap::new(),
+            true,
+            true,
+            &version_tags
+        )
+        .unwrap(),
+        vec![
+            manifest::Upgrade {
+                dep: "https://a.git".to_string(),
+                from: req("^1.2"),
+                to: req("^1.5.1"),
+            },
+            manifest::Upgrade {
+                dep: "b".to_string(),
+                from: req("~0.3"),
+                to: req("~0.3.4"),
+            },
+        ]
+    );
+    assert_eq!(
+        fs::read_to_string(tmp_dir.path().join(CFG_FILE_NAME)).unwrap(),
+        cfg
+    );
+    manifest::upgrade_deps(
+        tmp_dir.path(),
+        &["a".to_string()],
+        &HashMap::from([("a".to_string(), "https://a.git".to_string())]),
+        false,
+        false,
+        &version_tags,
+    )
+    .unwrap();
+    assert_eq!(
+        fs::read_to_string(tmp_dir.path().join(CFG_FILE_NAME)).unwrap(),
+        cfg.replace(r#""^1.2""#, r#""^2.0.0""#)
+            .replace(r#""=1.2.0""#, r#""=2.0.0""#)
+    );
+    let version_tags = |_: &str| {
+        Ok(["1.5.0", "3.1.0"]
+            .iter()
+            .map(|x| (Version::parse(x).unwrap(), String::new()))
+            .collect())
+    };
+    for (from, compatible, to) in [
+        ("<2", false, Some("^3.1.0")),
+        ("<2", true, Some("^1.5.0")),
+        ("<=2", false, Some("^3.1.0")),
+        (">1", false, Some("^3.1.0")),
+        ("*", true, Some("^3.1.0")),
+        (">=1, <2", true, Some("^1.5.0")),
+        (">=1", false, Some(">=3.1.0")),
+        ("~1.5", true, None),
+        ("1.5", true, None),
+        ("=1.5.0", true, None),
+        ("1.4", true, Some("^1.5.0")),
+    ] {
+        fs::write(
+            tmp_dir.path().join(CFG_FILE_NAME),
+            format!("name = \"package_name\"\n[[deps]]\nrepo = \"https://a.git\"\nversion = \"{from}\"\n"),
+        )
+        .unwrap();
+        assert_eq!(
+            manifest::upgrade_deps(
+                tmp_dir.path(),
+                &[],
+                &HashMap::new(),
+                compatible,
+                true,
+                &version_tags
+            )
+            .unwrap()
+            .into_iter()
+            .map(|x| x.to)
+            .collect::<Vec<_>>(),
+            to.map(req).into_iter().collect::<Vec<_>>(),
+            "{from} {compatible}"
+        );
+    }
+    let cfg = "name = \"package_name\"\n[[deps]]\nrepo = \"https://a.git\"\nversion = \"1.5\"\n";
+    fs::write(tmp_dir.path().join(CFG_FILE_NAME), cfg).unwrap();
+    assert!(manifest::upgrade_deps(
+        tmp_dir.path(),
+        &[],
+        &HashMap::new(),
+        true,
+        false,
+        &version_tags
+    )
+    .unwrap()
+    .is_empty());
+    assert_eq!(
+        fs::read_to_string(tmp_dir.path().join(CFG_FILE_NAME)).unwrap(),
+        cfg
+    );
+}
+
+#[test]
+fn outdated_t_1() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"
+        name = "package_name"
+
+        [[deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+
+        [[dev_deps]]
+        name = "other_b"
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        branch = "b"
+        "#,
+    )
+    .unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &tmp_dir.path().join("deps"),
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    let mut build_file = BuildFile::new(tmp_dir.path()).unwrap();
+    let req = semver::VersionReq::parse("^1.2").unwrap();
+    for unit in build_file.sccs.iter_mut().flatten() {
+        if unit.root {
+            unit.dep_reqs.insert(
+                "otherFiles".to_string(),
+                DepReq {
+                    option_name: None,
+                    when: None,
+                    version: Some(req.clone()),
+                },
+            );
+        } else if unit.lock.as_ref().unwrap().lock_type == Branch("default".to_string()) {
+            unit.version = Some(Version::new(1, 2, 0));
+        }
+    }
+    let outdated = outdated::outdated(
+        &LockFile::new(tmp_dir.path()).unwrap(),
+        &build_file,
+        &|_, branch| Ok(format!("{branch}_commit")),
+        &|_| {
+            Ok(["1.2.0", "1.5.1", "2.0.0", "3.0.0-rc.1"]
+                .iter()
+                .map(|x| (Version::parse(x).unwrap(), String::new()))
+                .collect())
+        },
+    )
+    .unwrap();
+    let lock = |branch: &str| LockUnit {
+        repo: "https://github.com/WinstonMDP/githubOtherFiles.git".to_string(),
+        lock_type: Branch(branch.to_string()),
+    };
+    assert_eq!(
+        outdated,
+        vec![
+            outdated::Outdated {
+                lock: lock("b"),
+                version: None,
+                reqs: vec![],
+                branch_commit: Some("b_commit".to_string()),
+                compatible_version: None,
+                latest_version: None,
+            },
+            outdated::Outdated {
+                lock: lock("default"),
+                version: Some(Version::new(1, 2, 0)),
+                reqs: vec![req],
+                branch_commit: Some("default_commit".to_string()),
+                compatible_version: Some(Version::new(1, 5, 1)),
+                latest_version: Some(Version::new(2, 0, 0)),
+            },
+        ]
+    );
+    let mut buffer = vec![];
+    outdated::table(&outdated, &mut buffer).unwrap();
+    assert_eq!(
+        String::from_utf8(buffer).unwrap(),
+        "\
+repo                                                lock            version  branch commit   compatible  latest
+https://github.com/WinstonMDP/githubOtherFiles.git  branch b        -        b_commit        -           -
+https://github.com/WinstonMDP/githubOtherFiles.git  branch default  1.2.0    default_commit  1.5.1       2.0.0
+"
+    );
+}
+
+#[test]
+fn project_t_1() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let project_dir = tmp_dir.path().join("project");
+    fs::create_dir_all(project_dir.join("src")).unwrap();
+    fs::write(project_dir.join(".gitignore"), "/target\n").unwrap();
+    project::init(
+        &project_dir,
+        "package_name",
+        project::ProjectKind::Lib,
+        Some(Path::new("/bin/sanskrit")),
+    )
+    .unwrap();
+    assert_eq!(
+        fs::read_to_string(project_dir.join(CFG_FILE_NAME)).unwrap(),
+        "name = \"package_name\"\ninterpreter = \"/bin/sanskrit\"\n"
+    );
+    assert!(project_dir.join("src").join("lib.sk").exists());
+    assert!(!project_dir.join("src").join("main.sk").exists());
+    assert_eq!(
+        fs::read_to_string(project_dir.join(".gitignore")).unwrap(),
+        "/target\n"
+    );
+    Cfg::new(&project_dir).unwrap();
+    assert!(project::init(&project_dir, "1package", project::ProjectKind::Bin, None).is_err());
+    let template_dir = tmp_dir.path().join("template");
+    fs::create_dir_all(template_dir.join("src").join(".git")).unwrap();
+    fs::write(template_dir.join(CFG_FILE_NAME), "name = \"{{name}}\"\n").unwrap();
+    fs::write(template_dir.join("src").join("{{name}}.sk"), "{{name}}").unwrap();
+    let new_dir = tmp_dir.path().join("new");
+    project::from_template(&new_dir, "new_name", template_dir.to_str().unwrap()).unwrap();
+    assert_eq!(
+        fs::read_to_string(new_dir.join(CFG_FILE_NAME)).unwrap(),
+        "name = \"new_name\"\n"
+    );
+    assert_eq!(
+        fs::read_to_string(new_dir.join("src").join("new_name.sk")).unwrap(),
+        "new_name"
+    );
+    assert!(!new_dir.join("src").join(".git").exists());
+    assert!(project::from_template(&new_dir, "new_name", template_dir.to_str().unwrap()).is_err());
+}
+
+#[test]
+fn build_file_t_1() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        tmp_dir.path().join(BUILD_FILE_NAME),
+        r#"{"version": 6, "sccs": []}"#,
+    )
+    .unwrap();
+    assert!(BuildFile::new(tmp_dir.path())
+        .unwrap_err()
+        .to_string()
+        .ends_with(&format!(
+            "build file has 6 version instead of {BUILD_FILE_VERSION}. Reinstall deps."
+        )));
+}
+
+#[test]
+fn install_t_29() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"
+        name = "package_name"
+
+        [[deps]]
+        repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+        "#,
+    )
+    .unwrap();
+    let installer = |_: &Path, dep_dir_path: &Path, _: &LockUnit| {
+        fs::create_dir(dep_dir_path)?;
+        fs::write(
+            dep_dir_path.join(CFG_FILE_NAME),
+            "name = \"otherFiles\"\n[[deps]]\npath = \"..\"\n",
+        )?;
+        Ok(())
+    };
+    assert_eq!(
+        cfg_install(
+            tmp_dir.path(),
+            &tmp_dir.path().join("deps"),
+            &HashSet::new(),
+            &HashSet::new(),
+            false,
+            &BTreeSet::new(),
+            false,
+            &installer,
+        )
+        .unwrap_err()
+        .to_string(),
+        r#"".." path dep isn't allowed in "WinstonMDP.githubOtherFiles.branch.default" git dep cfg."#
+    );
+}
+
+#[test]
+fn project_t_2() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let project_dir = tmp_dir.path().join("project_name");
+    fs::create_dir_all(project_dir.join("src")).unwrap();
+    fs::write(project_dir.join("src").join("main.sk"), "main").unwrap();
+    project::init_existing(&project_dir, None, project::ProjectKind::Bin, None).unwrap();
+    assert_eq!(
+        fs::read_to_string(project_dir.join(CFG_FILE_NAME)).unwrap(),
+        "name = \"project_name\"\n"
+    );
+    assert_eq!(
+        fs::read_to_string(project_dir.join("src").join("main.sk")).unwrap(),
+        "main"
+    );
+    let member_dir = project_dir.join("member");
+    fs::create_dir(&member_dir).unwrap();
+    assert_eq!(
+        project::init_existing(&member_dir, None, project::ProjectKind::Bin, None)
+            .unwrap_err()
+            .to_string(),
+        format!("{:#?} already exists.", project_dir.join(CFG_FILE_NAME))
+    );
+    assert!(!member_dir.join(CFG_FILE_NAME).exists());
+    let invalid_dir = tmp_dir.path().join("1project");
+    fs::create_dir(&invalid_dir).unwrap();
+    assert_eq!(
+        project::init_existing(&invalid_dir, None, project::ProjectKind::Bin, None)
+            .unwrap_err()
+            .to_string(),
+        "Use --name to set another name."
+    );
+    project::init_existing(
+        &invalid_dir,
+        Some("project"),
+        project::ProjectKind::Bin,
+        None,
+    )
+    .unwrap();
+}
+
+#[test]
+#[allow(clippy::too_many_lines)]
+fn install_t_30() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    for (name, cfg) in [
+        (
+            "p",
+            r#"
+            name = "p"
+            options = { x = [] }
+
+            [[deps]]
+            path = "../e"
+            when = "not(x)"
+            "#,
+        ),
+        (
+            "e",
+            r#"
+            name = "e"
+
+            [[deps]]
+            path = "../g"
+            options = ["g1"]
+            "#,
+        ),
+        (
+            "q",
+            r#"
+            name = "q"
+
+            [[deps]]
+            path = "../p"
+            options = ["x"]
+            "#,
+        ),
+        ("g", "name = \"g\"\noptions = { g1 = [] }\n"),
+        (
+            "a",
+            r#"
+            name = "a"
+            options = { a = [] }
+
+            [[deps]]
+            path = "../b"
+            when = "not(a)"
+            "#,
+        ),
+        (
+            "b",
+            r#"
+            name = "b"
+
+            [[deps]]
+            path = "../a"
+            options = ["a"]
+            "#,
+        ),
+    ] {
+        fs::create_dir(tmp_dir.path().join(name)).unwrap();
+        fs::write(tmp_dir.path().join(name).join(CFG_FILE_NAME), cfg).unwrap();
+    }
+    let project_dir = tmp_dir.path().join("project");
+    fs::create_dir(&project_dir).unwrap();
+    for names in [["p", "q", "g"], ["q", "p", "g"]] {
+        fs::write(
+            project_dir.join(CFG_FILE_NAME),
+            names
+                .iter()
+                .fold("name = \"package_name\"\n".to_string(), |cfg, x| {
+                    cfg + &format!("[[deps]]\npath = \"../{x}\"\n")
+                }),
+        )
+        .unwrap();
+        cfg_install(
+            &project_dir,
+            &project_dir.join("deps"),
+            &HashSet::new(),
+            &HashSet::new(),
+            false,
+            &BTreeSet::new(),
+            false,
+            &stub_installer,
+        )
+        .unwrap();
+        let build_file = BuildFile::new(&project_dir).unwrap();
+        let units: Vec<_> = build_file.sccs.iter().flatten().collect();
+        let unit = |name| units.iter().find(|x| x.name == name).unwrap();
+        assert_eq!(unit("p").options, BTreeSet::from(["x".to_string()]));
+        assert!(units.iter().all(|x| x.name != "e"));
+        assert_eq!(unit("g").options, BTreeSet::new());
+    }
+    fs::write(
+        project_dir.join(CFG_FILE_NAME),
+        "name = \"package_name\"\n[[deps]]\npath = \"../a\"\n",
+    )
+    .unwrap();
+    assert_eq!(
+        cfg_install(
+            &project_dir,
+            &project_dir.join("deps"),
+            &HashSet::new(),
+            &HashSet::new(),
+            false,
+            &BTreeSet::new(),
+            false,
+            &stub_installer,
+        )
+        .unwrap_err()
+        .to_string(),
+        "Options of deps don't converge because of not(...) in whens."
+    );
+}
+
+#[test]
+fn install_t_31() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let parser_dir = tmp_dir.path().join("parser");
+    fs::create_dir(&parser_dir).unwrap();
+    fs::write(
+        parser_dir.join(CFG_FILE_NAME),
+        "name = \"parser\"\noptions = { unicode = [] }\n",
+    )
+    .unwrap();
+    let project_dir = tmp_dir.path().join("project");
+    fs::create_dir(&project_dir).unwrap();
+    fs::write(
+        project_dir.join(CFG_FILE_NAME),
+        r#"
+        name = "package_name"
+        options = { default = ["parser/unicode"] }
+
+        [[deps]]
+        path = "../parser"
+        option_name = "parse"
+        "#,
+    )
+    .unwrap();
+    let install = |options: &[&str]| {
+        cfg_install(
+            &project_dir,
+            &project_dir.join("deps"),
+            &options.iter().map(ToString::to_string).collect(),
+            &HashSet::new(),
+            false,
+            &BTreeSet::new(),
+            false,
+            &stub_installer,
+        )
+    };
+    install(&[]).unwrap();
+    assert_eq!(build_file(&project_dir).len(), 1);
+    install(&["parse"]).unwrap();
+    let sccs = build_file(&project_dir);
+    assert_eq!(sccs[0][0].name, "parser");
+    assert_eq!(sccs[0][0].options, BTreeSet::from(["unicode".to_string()]));
+}
+
+#[test]
+fn install_t_32() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"
+        name = "package_name"
+
+        [[dev_deps]]
+        repo = "https://a.git"
+        subdir = "dev"
+
+        [[groups.bench.deps]]
+        repo = "https://a.git"
+        subdir = "bench"
+        "#,
+    )
+    .unwrap();
+    let installer = |_: &Path, dep_dir_path: &Path, _: &LockUnit| {
+        for name in ["dev", "bench"] {
+            fs::create_dir_all(dep_dir_path.join(name))?;
+            fs::write(
+                dep_dir_path.join(name).join(CFG_FILE_NAME),
+                format!("name = \"{name}\""),
+            )?;
+        }
+        Ok(())
+    };
+    cfg_install(
+        tmp_dir.path(),
+        &tmp_dir.path().join("deps"),
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::from(["bench".to_string()]),
+        false,
+        &installer,
+    )
+    .unwrap();
+    let lock_file = LockFile::new(tmp_dir.path()).unwrap();
+    let lock = LockUnit {
+        repo: "https://a.git".to_string(),
+        lock_type: Branch("default".to_string()),
+    };
+    assert!(lock_file.locks.is_empty());
+    assert_eq!(lock_file.dev_locks, vec![lock.clone()]);
+    assert_eq!(
+        lock_file.group_locks,
+        BTreeMap::from([("bench".to_string(), vec![lock])])
+    );
+}
+
+#[test]
+fn install_t_33() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let member_dir = tmp_dir.path().join("member");
+    fs::create_dir(&member_dir).unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"
+        name = "package_name"
+
+        [workspace]
+        members = ["member"]
+
+        [[deps]]
+        repo = "https://a.git"
+        "#,
+    )
+    .unwrap();
+    fs::write(member_dir.join(CFG_FILE_NAME), r#"name = "member""#).unwrap();
+    let installer = |_: &Path, dep_dir_path: &Path, _: &LockUnit| {
+        fs::create_dir(dep_dir_path)?;
+        fs::write(dep_dir_path.join(CFG_FILE_NAME), "name = \"a\"\nxyz = 1")?;
+        Ok(())
+    };
+    let install = |lenient| {
+        cfg_install(
+            tmp_dir.path(),
+            &tmp_dir.path().join("deps"),
+            &HashSet::new(),
+            &HashSet::new(),
+            false,
+            &BTreeSet::new(),
+            lenient,
+            &installer,
+        )
+    };
+    let dep_warning = format!(
+        "Warning in {:#?} cfg file. Unknown \"xyz\" key of the root at line 2, column 1.",
+        tmp_dir
+            .path()
+            .join("deps")
+            .join(
+                dep_dir(&LockUnit {
+                    repo: "https://a.git".to_string(),
+                    lock_type: Branch("default".to_string()),
+                })
+                .unwrap()
+            )
+            .join(CFG_FILE_NAME)
+    );
+    assert_eq!(install(false).unwrap(), vec![dep_warning.clone()]);
+    fs::write(member_dir.join(CFG_FILE_NAME), "name = \"member\"\nxyz = 1").unwrap();
+    assert!(install(false).is_err());
+    let member_warning = format!(
+        "Warning in {:#?} cfg file. Unknown \"xyz\" key of the root at line 2, column 1.",
+        fs::canonicalize(&member_dir).unwrap().join(CFG_FILE_NAME)
+    );
+    assert_unord_eq(
+        &install(true).unwrap(),
+        &[dep_warning.clone(), member_warning],
+    );
+    fs::write(member_dir.join(CFG_FILE_NAME), r#"name = "member""#).unwrap();
+    let cfg = fs::read_to_string(tmp_dir.path().join(CFG_FILE_NAME)).unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        format!("xyz = 1\n{cfg}"),
+    )
+    .unwrap();
+    assert!(install(false).is_err());
+    assert_eq!(install(true).unwrap().len(), 2);
+}
+
+#[test]
+fn readme_t_1() {
+    let readme =
+        fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("README.md")).unwrap();
+    let cfg = readme
+        .split("```toml\n")
+        .nth(1)
+        .unwrap()
+        .split("```")
+        .next()
+        .unwrap();
+    let tmp_dir = tempfile::tempdir().unwrap();
+    fs::write(tmp_dir.path().join(CFG_FILE_NAME), cfg).unwrap();
+    let (errors, warnings) = manifest::check_manifest(tmp_dir.path(), false);
+    assert!(
+        errors.is_empty() && warnings.is_empty(),
+        "{errors:?} {warnings:?}"
+    );
+    let cfg = Cfg::new(tmp_dir.path()).unwrap();
+    check_requirements(
+        &[Requirements {
+            interpreter_version: None,
+            ..cfg.requirements()
+        }],
+        Path::new(""),
+    )
+    .unwrap();
+    let mut deps = [cfg.deps.as_slice(), cfg.dev_deps.as_slice()].concat();
+    deps.extend(cfg.groups.into_values().flat_map(|x| x.deps));
+    let options = resolve_options(&cfg.options, &deps, ["full".to_string()]).unwrap();
+    for dep_name in options.dep_options.keys() {
+        assert!(deps.iter().any(|x| x.name.as_ref() == Some(dep_name)));
+    }
+}
+
+#[test]
+fn install_t_34() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"
+        name = "package_name"
+
+        [[deps]]
+        name = "b1"
+        repo = "https://a.git"
+        subdir = "a/./b/"
+
+        [[deps]]
+        name = "b2"
+        repo = "https://a.git"
+        subdir = "a/b"
+        "#,
+    )
+    .unwrap();
+    let installer = |_: &Path, dep_dir_path: &Path, _: &LockUnit| {
+        fs::create_dir_all(dep_dir_path.join("a").join("b"))?;
+        fs::write(dep_dir_path.join(CFG_FILE_NAME), r#"name = "a""#)?;
+        fs::write(
+            dep_dir_path.join("a").join("b").join(CFG_FILE_NAME),
+            r#"name = "b""#,
+        )?;
+        Ok(())
+    };
+    let install = || {
+        cfg_install(
+            tmp_dir.path(),
+            &tmp_dir.path().join("deps"),
+            &HashSet::new(),
+            &HashSet::new(),
+            false,
+            &BTreeSet::new(),
+            false,
+            &installer,
+        )
+    };
+    install().unwrap();
+    let dir = Path::new(
+        &dep_dir(&LockUnit {
+            repo: "https://a.git".to_string(),
+            lock_type: Branch("default".to_string()),
+        })
+        .unwrap(),
+    )
+    .join("a")
+    .join("b")
+    .into_os_string();
+    let units: Vec<BuildUnit> = build_file(tmp_dir.path()).into_iter().flatten().collect();
+    assert_eq!(units.len(), 2);
+    let root = units.iter().find(|x| x.root).unwrap();
+    assert_eq!(
+        root.name_map,
+        BTreeMap::from([("b1".to_string(), dir.clone()), ("b2".to_string(), dir)])
+    );
+    let cfg = fs::read_to_string(tmp_dir.path().join(CFG_FILE_NAME)).unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        cfg.replace(r#"subdir = "a/b""#, r#"subdir = """#),
+    )
+    .unwrap();
+    assert!(install().is_err());
+}
+
+#[test]
+fn install_t_35() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let member_dir = tmp_dir.path().join("member");
+    fs::create_dir(&member_dir).unwrap();
+    fs::write(
+        tmp_dir.path().join(CFG_FILE_NAME),
+        r#"
+        name = "package_name"
+
+        [workspace]
+        members = ["member"]
+
+        [[deps]]
+        name = "a"
+        repo = "https://a.git"
+        "#,
+    )
+    .unwrap();
+    fs::write(member_dir.join(CFG_FILE_NAME), r#"name = "member""#).unwrap();
+    let installer = |_: &Path, dep_dir_path: &Path, _: &LockUnit| {
+        fs::create_dir(dep_dir_path)?;
+        fs::write(
+            dep_dir_path.join(CFG_FILE_NAME),
+            "name = \"my.pkg\"\nhomepage = \"ftp://example.com\"",
+        )?;
+        Ok(())
+    };
+    let install = || {
+        cfg_install(
+            tmp_dir.path(),
+            &tmp_dir.path().join("deps"),
+            &HashSet::new(),
+            &HashSet::new(),
+            false,
+            &BTreeSet::new(),
+            true,
+            &installer,
+        )
+    };
+    let warnings = install().unwrap();
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0].contains(r#"Invalid "name" key of the root at line 1, column 1."#));
+    assert!(warnings[1].contains(r#"Invalid "homepage" key of the root at line 2, column 1."#));
+    fs::write(
+        member_dir.join(CFG_FILE_NAME),
+        "name = \"member\"\nkeywords = [\"no std\"]",
+    )
+    .unwrap();
+    assert!(install().is_err());
+    let (errors, _) = manifest::check_manifest(tmp_dir.path(), true);
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains(r#"Invalid "keywords" key of the root at line 2, column 1."#));
+}
+
+#[test]
+fn clean_t_1() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let cfg = tmp_dir.path().join(CFG_FILE_NAME);
+    fs::write(
+        &cfg,
+        r#"
+            name = "package_name"
+
+            [[deps]]
+            repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+            "#,
+    )
+    .unwrap();
+    let deps_dir = tmp_dir.path().join("deps");
+    fs::create_dir(&deps_dir).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    fs::write(
+        cfg,
+        r#"
+            name = "package_name"
+            "#,
+    )
+    .unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    clean(
+        &LockFile::new(tmp_dir.path()).unwrap().locks,
+        tmp_dir.path(),
+        &deps_dir,
+        &mut empty(),
+    )
+    .unwrap();
+    assert!(!Path::exists(
+        &deps_dir.join("WinstonMDP.githubOtherFiles.branch.default")
+    ));
+    assert_eq!(nfiles(&deps_dir), 0);
+}
+
+#[test]
+fn clean_t_2() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let cfg = tmp_dir.path().join(CFG_FILE_NAME);
+    fs::write(
+        &cfg,
+        r#"
+            name = "package_name"
+
+            [[deps]]
+            repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+
+            [[deps]]
+            name = "package_name_to_clean"
+            repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+            branch = "b"
+            "#,
+    )
+    .unwrap();
+    let deps_dir = tmp_dir.path().join("deps");
+    fs::create_dir(&deps_dir).unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
+    )
+    .unwrap();
+    assert!(Path::exists(
+        &deps_dir.join("WinstonMDP.githubOtherFiles.branch.default")
+    ));
+    fs::write(
+        cfg,
+        r#"
+            name = "package_name"
+
+            [[deps]]
+            repo = "https://github.com/WinstonMDP/githubOtherFiles.git"
+            "#,
+    )
+    .unwrap();
+    cfg_install(
+        tmp_dir.path(),
+        &deps_dir,
+        &HashSet::new(),
+        &HashSet::new(),
+        false,
+        &BTreeSet::new(),
+        false,
+        &stub_installer,
     )
     .unwrap();
-    cfg_install(tmp_dir.path(), &deps_dir, &HashSet::new(), &stub_installer).unwrap();
     clean(
         &LockFile::new(tmp_dir.path()).unwrap().locks,
+        tmp_dir.path(),
         &deps_dir,
         &mut empty(),
     )